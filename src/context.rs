@@ -0,0 +1,50 @@
+use crate::graph::Graph;
+use crate::node::NodeId;
+use crate::rng::{RngStream, Sampler};
+use crate::value::Value;
+
+/// Read access to the graph handed to contextual node functions while they run, see
+/// [`Graph::contextual_node`].
+pub struct EvalContext<'a, T = f32> {
+    graph: &'a Graph<T>,
+    node: NodeId,
+}
+
+impl<'a, T: Value> EvalContext<'a, T> {
+    pub(crate) fn new(graph: &'a Graph<T>, node: NodeId) -> Self {
+        Self { graph, node }
+    }
+
+    /// The node being evaluated.
+    pub fn node(&self) -> NodeId {
+        self.node
+    }
+
+    /// Random stream of this node for the current evaluation, keyed by the seed of the graph, the
+    /// node and the evaluation count. Each call restarts the same stream, so draw every value of
+    /// an evaluation from one stream. Nodes drawing from it should be marked impure.
+    pub fn rng(&self) -> RngStream {
+        let (seed, evaluation) = self.graph.rng_key();
+        RngStream::new(seed, self.node.0 as u64, evaluation)
+    }
+
+    /// Like [`EvalContext::rng`], drawing values in the sampling mode of the node, see
+    /// [`Graph::set_sampling`].
+    pub fn sampler(&self) -> Sampler {
+        let (seed, evaluation) = self.graph.rng_key();
+        let mode = self.graph.inner(self.node).sampling;
+        Sampler::new(mode, seed, self.node.0 as u64, evaluation)
+    }
+
+    /// Output of another node if it is already computed and up to date. Nodes that are not
+    /// descendants of the evaluated one are not computed on demand, so the result depends on
+    /// what was requested before. Nodes restricted to a capability are never read, the attempt is
+    /// recorded like a refused request.
+    pub fn output(&self, node: NodeId) -> Option<&'a [T]> {
+        self.graph.authorize(node, &[]).ok()?;
+        match self.graph.get(node) {
+            Ok(inner) if !inner.is_dirty() => inner.output(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use std::sync::Arc;
+
+use crate::error::NodeError;
+
+/// Replacement of one element of a node's input or output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change<T = f32> {
+    pub index: usize,
+    pub old: T,
+    pub new: T,
+}
+
+// Updates the previous output of a node for changes to its input and reports the changes made to
+// the output.
+pub(crate) type UpdateFn<T> =
+    Arc<dyn Fn(&mut Vec<T>, &[Change<T>]) -> Result<Vec<Change<T>>, NodeError> + Send + Sync>;
@@ -0,0 +1,94 @@
+use std::fmt::Write;
+
+use crate::graph::Graph;
+use crate::value::Value;
+
+impl<T: Value> Graph<T> {
+    /// Graphviz description of the graph. Edges follow the data flow from child to parent and
+    /// every node is labelled with its id, name, op key and whether its output is up to date.
+    /// Descriptions are shown as tooltips.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph {\n");
+        for node in self.nodes() {
+            let mut label = node.to_string();
+            if let Some(name) = self.name(node) {
+                label.push(' ');
+                label.push_str(name);
+            }
+            if let Some(op) = self.op(node) {
+                label.push('\n');
+                label.push_str(op);
+            }
+            let state = if self.inner(node).is_dirty() {
+                "dirty"
+            } else {
+                "cached"
+            };
+            let tooltip = match self.description(node) {
+                Some(description) => format!(", tooltip={}", quote(description)),
+                None => String::new(),
+            };
+            let _ = writeln!(
+                dot,
+                "    n{} [label={}{}];",
+                node.0,
+                quote(&format!("{}\n{}", label, state)),
+                tooltip
+            );
+        }
+        for node in self.nodes() {
+            for child in self.inner(node).down.iter() {
+                let _ = writeln!(dot, "    n{} -> n{};", child.0, node.0);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// Quotes `text` as a DOT string, lines are separated with the `\n` escape.
+fn quote(text: &str) -> String {
+    let mut res = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_dot() {
+        let mut graph = Graph::new();
+        let x = graph
+            .add_node_named("x \"in\" a\\b", |input| input)
+            .unwrap();
+        let y = graph
+            .node(|input| vec![input.iter().sum()])
+            .child(x)
+            .unwrap()
+            .description("total of x, in €")
+            .build();
+        graph.input(x).unwrap().set(vec![1.0]);
+        graph.compute(y).unwrap();
+        graph.input(x).unwrap().set(vec![2.0]);
+        graph.compute(x).unwrap();
+
+        assert_eq!(
+            graph.to_dot(),
+            "digraph {\n    \
+            n0 [label=\"#0 x \\\"in\\\" a\\\\b\\ncached\"];\n    \
+            n1 [label=\"#1\\ndirty\", tooltip=\"total of x, in €\"];\n    \
+            n0 -> n1;\n\
+            }\n"
+        );
+    }
+}
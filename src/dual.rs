@@ -0,0 +1,215 @@
+//! Forward-mode differentiation with dual numbers.
+
+use std::ops::{Add, Div, Mul, Sub};
+
+use crate::error::GraphError;
+use crate::graph::Graph;
+use crate::node::NodeId;
+use crate::ops::Scalar;
+
+/// Value paired with its derivative along some direction. Graphs over `Dual` built from
+/// [`ops`](crate::ops) carry derivatives alongside values, see [`Graph::jvp`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dual {
+    pub value: f32,
+    pub tangent: f32,
+}
+
+impl Dual {
+    pub fn new(value: f32, tangent: f32) -> Self {
+        Self { value, tangent }
+    }
+
+    /// A value that does not vary along the direction of differentiation.
+    pub fn constant(value: f32) -> Self {
+        Self::new(value, 0.0)
+    }
+}
+
+impl Add for Dual {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.value + other.value, self.tangent + other.tangent)
+    }
+}
+
+impl Sub for Dual {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.value - other.value, self.tangent - other.tangent)
+    }
+}
+
+impl Mul for Dual {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.value * other.value,
+            self.tangent * other.value + self.value * other.tangent,
+        )
+    }
+}
+
+impl Div for Dual {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        Self::new(
+            self.value / other.value,
+            (self.tangent * other.value - self.value * other.tangent) / (other.value * other.value),
+        )
+    }
+}
+
+impl Scalar for Dual {
+    fn zero() -> Self {
+        Self::constant(0.0)
+    }
+
+    fn sin(self) -> Self {
+        Self::new(self.value.sin(), self.value.cos() * self.tangent)
+    }
+
+    fn powf(self, n: f32) -> Self {
+        Self::new(
+            self.value.powf(n),
+            n * self.value.powf(n - 1.0) * self.tangent,
+        )
+    }
+}
+
+impl Graph<Dual> {
+    /// Derivative of the output of `node` when the external input of `input` moves along
+    /// `tangent`, at the current input values. Tangents stored in the graph are ignored, the
+    /// graph itself is left untouched. `tangent` must have one value per value of that input.
+    pub fn jvp(
+        &self,
+        node: NodeId,
+        input: NodeId,
+        tangent: &[f32],
+    ) -> Result<Vec<f32>, GraphError> {
+        let seed = self
+            .get(input)?
+            .input
+            .as_ref()
+            .ok_or(GraphError::NoInput(input))?;
+        if seed.len() != tangent.len() {
+            return Err(GraphError::LengthMismatch {
+                node: input,
+                expected: seed.len(),
+                actual: tangent.len(),
+            });
+        }
+        let mut seeded = self.clone();
+        for id in self.nodes() {
+            let Some(values) = self.inner(id).input.as_ref() else {
+                continue;
+            };
+            let values = values
+                .iter()
+                .enumerate()
+                .map(|(index, x)| match id == input {
+                    true => Dual::new(x.value, tangent[index]),
+                    false => Dual::constant(x.value),
+                })
+                .collect();
+            seeded.input(id)?.set(values);
+        }
+        let output = seeded.compute(node)?;
+        Ok(output.iter().map(|x| x.tangent).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops;
+
+    #[test]
+    fn test_jvp() {
+        // f(x, y) = sin(x)^3 * y + x
+        let mut graph = Graph::<Dual>::default();
+        let x = graph
+            .fallible_node(ops::identity())
+            .input(vec![Dual::constant(0.5)])
+            .build();
+        let y = graph
+            .fallible_node(ops::identity())
+            .input(vec![Dual::constant(2.0)])
+            .build();
+        let sin = graph.fallible_node(ops::sin()).child(x).unwrap().build();
+        let cube = graph
+            .fallible_node(ops::powf(3.0))
+            .child(sin)
+            .unwrap()
+            .build();
+        let product = graph
+            .fallible_node(ops::mul())
+            .child(cube)
+            .unwrap()
+            .child(y)
+            .unwrap()
+            .build();
+        let f = graph
+            .fallible_node(ops::add())
+            .child(product)
+            .unwrap()
+            .child(x)
+            .unwrap()
+            .build();
+
+        let (s, c) = (0.5f32.sin(), 0.5f32.cos());
+        let dx = graph.jvp(f, x, &[1.0]).unwrap();
+        assert!((dx[0] - (3.0 * s * s * c * 2.0 + 1.0)).abs() < 1e-6);
+        let dy = graph.jvp(f, y, &[2.0]).unwrap();
+        assert!((dy[0] - 2.0 * s.powi(3)).abs() < 1e-6);
+
+        assert_eq!(
+            graph.jvp(f, x, &[1.0, 0.0]),
+            Err(GraphError::LengthMismatch {
+                node: x,
+                expected: 1,
+                actual: 2
+            })
+        );
+        assert_eq!(graph.jvp(f, sin, &[1.0]), Err(GraphError::NoInput(sin)));
+        assert_eq!(
+            graph.jvp(f, NodeId(9), &[1.0]),
+            Err(GraphError::UnknownNode(NodeId(9)))
+        );
+    }
+
+    #[test]
+    fn test_quotient() {
+        // f(x, y) = (x - y) / y
+        let mut graph = Graph::<Dual>::default();
+        let x = graph
+            .fallible_node(ops::identity())
+            .input(vec![Dual::constant(3.0)])
+            .build();
+        let y = graph
+            .fallible_node(ops::identity())
+            .input(vec![Dual::constant(2.0)])
+            .build();
+        let diff = graph
+            .fallible_node(ops::sub())
+            .child(x)
+            .unwrap()
+            .child(y)
+            .unwrap()
+            .build();
+        let f = graph
+            .fallible_node(ops::div())
+            .child(diff)
+            .unwrap()
+            .child(y)
+            .unwrap()
+            .build();
+
+        assert_eq!(graph.jvp(f, x, &[1.0]).unwrap(), [0.5]);
+        assert_eq!(graph.jvp(f, y, &[1.0]).unwrap(), [-0.75]);
+    }
+}
@@ -0,0 +1,187 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::node::NodeId;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphError {
+    /// The id does not belong to this graph.
+    UnknownNode(NodeId),
+    /// No op is registered under this key.
+    UnknownOp(String),
+    /// `node` is a placeholder for an op that has not been resolved yet.
+    UnresolvedOp { node: NodeId, op: String },
+    /// The node was not created from a registered op, so it has no key to be stored under.
+    Unregistered(NodeId),
+    /// No candidate shadows `node`.
+    NoShadow(NodeId),
+    /// No input of `node` was recorded at or before the requested time.
+    NoHistory(NodeId),
+    /// Another node of the graph already has this name.
+    DuplicateName(String),
+    /// The evaluator found no output for `node` although it should have been computed.
+    NotComputed(NodeId),
+    /// `child` is not an input of `parent`.
+    NoEdge { parent: NodeId, child: NodeId },
+    /// Connecting another child would exceed the declared arity of `node`.
+    ArityExceeded { node: NodeId, arity: usize },
+    /// `node` has fewer children than its declared arity.
+    UnderConnected {
+        node: NodeId,
+        expected: usize,
+        actual: usize,
+    },
+    /// Wiring would make a node depend on itself. The path runs from the parent of the rejected
+    /// connection through its would-be descendants back to itself.
+    CycleDetected(Vec<NodeId>),
+    /// The function of `node` needed an input value at `index` that it did not receive.
+    MissingInput { node: NodeId, index: usize },
+    /// The function of `node` needed its `port` to be connected.
+    MissingPort { node: NodeId, port: String },
+    /// `node` has no port with this name.
+    UnknownPort { node: NodeId, port: String },
+    /// The function of `node` received a different number of input values than it accepts.
+    ArityMismatch {
+        node: NodeId,
+        expected: usize,
+        actual: usize,
+    },
+    /// `node` has no external input to be seeded.
+    NoInput(NodeId),
+    /// Values given for `node` differ in length from its external input.
+    LengthMismatch {
+        node: NodeId,
+        expected: usize,
+        actual: usize,
+    },
+    /// A stored definition uses a newer schema version than this build reads.
+    UnsupportedVersion(u32),
+    /// The function of `node` failed for another reason.
+    Node { node: NodeId, message: String },
+    /// The output of `node` was requested without a capability it is restricted to.
+    Unauthorized { node: NodeId, capability: String },
+}
+
+impl GraphError {
+    pub(crate) fn from_node(node: NodeId, error: NodeError) -> Self {
+        match error {
+            NodeError::MissingInput(index) => GraphError::MissingInput { node, index },
+            NodeError::MissingPort(port) => GraphError::MissingPort { node, port },
+            NodeError::ArityMismatch { expected, actual } => GraphError::ArityMismatch {
+                node,
+                expected,
+                actual,
+            },
+            NodeError::Failed(message) => GraphError::Node { node, message },
+        }
+    }
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::UnknownNode(node) => write!(f, "unknown node {}", node),
+            GraphError::UnknownOp(key) => write!(f, "unknown op {:?}", key),
+            GraphError::UnresolvedOp { node, op } => {
+                write!(f, "node {} uses unresolved op {:?}", node, op)
+            }
+            GraphError::Unregistered(node) => write!(f, "node {} has no registered op", node),
+            GraphError::NoShadow(node) => write!(f, "node {} has no shadow", node),
+            GraphError::NoHistory(node) => {
+                write!(
+                    f,
+                    "node {} has no input recorded at the requested time",
+                    node
+                )
+            }
+            GraphError::DuplicateName(name) => write!(f, "duplicate node name {:?}", name),
+            GraphError::NotComputed(node) => write!(f, "node {} has no output", node),
+            GraphError::NoEdge { parent, child } => {
+                write!(f, "node {} is not a child of {}", child, parent)
+            }
+            GraphError::ArityExceeded { node, arity } => {
+                write!(f, "node {} accepts at most {} children", node, arity)
+            }
+            GraphError::UnderConnected {
+                node,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "node {} expects {} children, has {}",
+                node, expected, actual
+            ),
+            GraphError::CycleDetected(path) => {
+                let path: Vec<String> = path.iter().map(NodeId::to_string).collect();
+                write!(f, "cycle detected: {}", path.join(" -> "))
+            }
+            GraphError::MissingInput { node, index } => {
+                write!(f, "node {} is missing input {}", node, index)
+            }
+            GraphError::MissingPort { node, port } => {
+                write!(f, "node {} has no input on port {:?}", node, port)
+            }
+            GraphError::UnknownPort { node, port } => {
+                write!(f, "node {} has no port {:?}", node, port)
+            }
+            GraphError::ArityMismatch {
+                node,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "node {} expects {} inputs, got {}",
+                node, expected, actual
+            ),
+            GraphError::NoInput(node) => write!(f, "node {} has no external input", node),
+            GraphError::LengthMismatch {
+                node,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "node {} has {} input values, got {}",
+                node, expected, actual
+            ),
+            GraphError::UnsupportedVersion(version) => {
+                write!(f, "unsupported definition version {}", version)
+            }
+            GraphError::Node { node, message } => write!(f, "node {} failed: {}", node, message),
+            GraphError::Unauthorized { node, capability } => {
+                write!(f, "node {} requires capability {}", node, capability)
+            }
+        }
+    }
+}
+
+impl Error for GraphError {}
+
+/// Error returned by a node function, the graph reports it as a [`GraphError`] naming the node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeError {
+    MissingInput(usize),
+    MissingPort(String),
+    ArityMismatch { expected: usize, actual: usize },
+    Failed(String),
+}
+
+impl NodeError {
+    pub fn failed(message: impl Into<String>) -> Self {
+        NodeError::Failed(message.into())
+    }
+}
+
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeError::MissingInput(index) => write!(f, "missing input {}", index),
+            NodeError::MissingPort(port) => write!(f, "missing input on port {:?}", port),
+            NodeError::ArityMismatch { expected, actual } => {
+                write!(f, "expects {} inputs, got {}", expected, actual)
+            }
+            NodeError::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+impl Error for NodeError {}
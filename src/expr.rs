@@ -0,0 +1,199 @@
+//! Arithmetic operators on node handles, building one node per operation.
+//!
+//! ```
+//! use computation_graph::Graph;
+//!
+//! let mut graph = Graph::new();
+//! let a = graph.node(|input| input).input(vec![3.0]).build();
+//! let b = graph.node(|input| input).input(vec![4.0]).build();
+//! let c = graph.expr(|scope| {
+//!     let (a, b) = (scope.handle(a)?, scope.handle(b)?);
+//!     Ok::<_, computation_graph::GraphError>((&a * &b + 2.0).id())
+//! });
+//! assert_eq!(graph.compute(c.unwrap()).unwrap(), [14.0]);
+//! ```
+
+use std::cell::RefCell;
+use std::ops::{Add, Div, Mul, Sub};
+
+use crate::error::{GraphError, NodeError};
+use crate::graph::Graph;
+use crate::node::NodeId;
+use crate::ops::{self, Scalar};
+
+/// Graph borrowed for building nodes with operators, see [`Graph::expr`].
+pub struct Scope<'g, T = f32> {
+    graph: RefCell<&'g mut Graph<T>>,
+}
+
+/// Node of a [`Scope`] that combines with other handles and constants through `+`, `-`, `*`
+/// and `/`. Each operation adds a node applying the binary op to the outputs of both operands,
+/// so each operand must produce a single value.
+pub struct Handle<'s, 'g, T = f32> {
+    scope: &'s Scope<'g, T>,
+    id: NodeId,
+}
+
+// Derived impls would require `T: Clone`, the handle itself only holds a reference and an id.
+impl<T> Clone for Handle<'_, '_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<'_, '_, T> {}
+
+impl<T> Handle<'_, '_, T> {
+    pub fn id(self) -> NodeId {
+        self.id
+    }
+}
+
+impl<T: Scalar> Graph<T> {
+    /// Runs `build` with a scope in which nodes are combined with arithmetic operators.
+    pub fn expr<R>(&mut self, build: impl FnOnce(&Scope<'_, T>) -> R) -> R {
+        build(&Scope {
+            graph: RefCell::new(self),
+        })
+    }
+}
+
+impl<'g, T: Scalar> Scope<'g, T> {
+    pub fn handle(&self, node: NodeId) -> Result<Handle<'_, 'g, T>, GraphError> {
+        self.graph.borrow().get(node)?;
+        Ok(Handle {
+            scope: self,
+            id: node,
+        })
+    }
+
+    /// Adds a node producing `value`.
+    pub fn constant(&self, value: T) -> Handle<'_, 'g, T> {
+        let id = self
+            .graph
+            .borrow_mut()
+            .fallible_node(ops::identity())
+            .input(vec![value])
+            .build();
+        Handle { scope: self, id }
+    }
+
+    fn apply<F>(
+        &self,
+        func: F,
+        children: &[Handle<'_, 'g, T>],
+        input: Option<T>,
+    ) -> Handle<'_, 'g, T>
+    where
+        F: Fn(Vec<T>) -> Result<Vec<T>, NodeError> + Send + Sync + 'static,
+    {
+        let mut graph = self.graph.borrow_mut();
+        let mut builder = graph.fallible_node(func);
+        for child in children {
+            assert!(
+                std::ptr::eq(child.scope, self),
+                "handles of different scopes cannot be combined"
+            );
+            // A new node cannot close a cycle and accepts any number of children.
+            builder = builder.child(child.id).expect("handles are valid children");
+        }
+        if let Some(value) = input {
+            builder = builder.input(vec![value]);
+        }
+        Handle {
+            scope: self,
+            id: builder.build(),
+        }
+    }
+}
+
+macro_rules! binary_op {
+    ($trait:ident, $method:ident, $op:path) => {
+        impl<'s, 'g, T: Scalar> $trait for Handle<'s, 'g, T> {
+            type Output = Self;
+
+            fn $method(self, other: Self) -> Self {
+                self.scope.apply($op(), &[self, other], None)
+            }
+        }
+
+        impl<'s, 'g, T: Scalar> $trait for &Handle<'s, 'g, T> {
+            type Output = Handle<'s, 'g, T>;
+
+            fn $method(self, other: Self) -> Handle<'s, 'g, T> {
+                (*self).$method(*other)
+            }
+        }
+
+        impl<'s, 'g, T: Scalar> $trait<T> for Handle<'s, 'g, T> {
+            type Output = Self;
+
+            fn $method(self, other: T) -> Self {
+                self.scope.apply($op(), &[self], Some(other))
+            }
+        }
+
+        impl<'s, 'g, T: Scalar> $trait<T> for &Handle<'s, 'g, T> {
+            type Output = Handle<'s, 'g, T>;
+
+            fn $method(self, other: T) -> Handle<'s, 'g, T> {
+                (*self).$method(other)
+            }
+        }
+
+        binary_op!(@scalar $trait, $method, f32);
+        binary_op!(@scalar $trait, $method, f64);
+    };
+    (@scalar $trait:ident, $method:ident, $scalar:ty) => {
+        impl<'s, 'g> $trait<Handle<'s, 'g, $scalar>> for $scalar {
+            type Output = Handle<'s, 'g, $scalar>;
+
+            fn $method(self, other: Handle<'s, 'g, $scalar>) -> Handle<'s, 'g, $scalar> {
+                other.scope.constant(self).$method(other)
+            }
+        }
+
+        impl<'s, 'g> $trait<&Handle<'s, 'g, $scalar>> for $scalar {
+            type Output = Handle<'s, 'g, $scalar>;
+
+            fn $method(self, other: &Handle<'s, 'g, $scalar>) -> Handle<'s, 'g, $scalar> {
+                self.$method(*other)
+            }
+        }
+    };
+}
+
+binary_op!(Add, add, ops::add);
+binary_op!(Sub, sub, ops::sub);
+binary_op!(Mul, mul, ops::mul);
+binary_op!(Div, div, ops::div);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_operators() {
+        let mut graph = Graph::new();
+        let a = graph.node(|input| input).input(vec![3.0]).build();
+        let b = graph.node(|input| input).input(vec![4.0]).build();
+        let (c, d) = graph
+            .expr(|scope| -> Result<_, GraphError> {
+                let (a, b) = (scope.handle(a)?, scope.handle(b)?);
+                let c = a * b + 2.0;
+                let d = 1.0 - (c - a) / b * 2.0;
+                Ok((c.id(), d.id()))
+            })
+            .unwrap();
+        assert_eq!(graph.compute(c).unwrap(), [14.0]);
+        assert_eq!(graph.compute(d).unwrap(), [-4.5]);
+
+        graph.input(b).unwrap().set(vec![1.0]);
+        assert_eq!(graph.compute(d).unwrap(), [-3.0]);
+        // Two nodes for c, then a constant and four nodes for d.
+        assert_eq!(graph.len(), 9);
+
+        let missing = graph.expr(|scope| scope.handle(NodeId(9)).err());
+        assert_eq!(missing, Some(GraphError::UnknownNode(NodeId(9))));
+    }
+}
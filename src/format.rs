@@ -0,0 +1,178 @@
+//! Rendering of node outputs for reports, according to a format attached to each node.
+
+use crate::error::GraphError;
+use crate::graph::Graph;
+use crate::node::NodeId;
+use crate::value::Value;
+
+/// How the values of a node are rendered, see [`Graph::set_format`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Format {
+    /// Fixed number of decimal places.
+    Decimal(usize),
+    /// Value times 100 with the given decimal places, followed by `%`.
+    Percent(usize),
+    /// Amount prefixed by a currency symbol.
+    Currency { symbol: String, places: usize },
+    /// Mantissa at least 1 and below 1000 with the given decimal places, and an exponent that
+    /// is a multiple of 3.
+    Engineering(usize),
+}
+
+/// Separators used when rendering numbers. The default locale uses a dot and no grouping.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Locale {
+    pub decimal_mark: char,
+    /// Inserted between groups of three digits of the integer part, for decimal, percent and
+    /// currency formats.
+    pub group_separator: Option<char>,
+}
+
+impl Locale {
+    pub const EN: Locale = Locale {
+        decimal_mark: '.',
+        group_separator: Some(','),
+    };
+    pub const DE: Locale = Locale {
+        decimal_mark: ',',
+        group_separator: Some('.'),
+    };
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            decimal_mark: '.',
+            group_separator: None,
+        }
+    }
+}
+
+impl Format {
+    pub fn apply(&self, x: f64, locale: &Locale) -> String {
+        if !x.is_finite() {
+            return x.to_string();
+        }
+        match self {
+            Format::Decimal(places) => fixed(x, *places, locale, ""),
+            Format::Percent(places) => fixed(x * 100.0, *places, locale, "") + "%",
+            Format::Currency { symbol, places } => fixed(x, *places, locale, symbol),
+            Format::Engineering(places) => engineering(x, *places, locale),
+        }
+    }
+}
+
+// Rounds `x` to `places` decimals, grouping the integer part, with `prefix` after the sign.
+fn fixed(x: f64, places: usize, locale: &Locale, prefix: &str) -> String {
+    let digits = format!("{:.*}", places, x.abs());
+    let (integer, fraction) = match digits.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (digits.as_str(), None),
+    };
+    // Values rounding to zero are rendered without a sign.
+    let negative = x < 0.0 && digits.bytes().any(|digit| (b'1'..=b'9').contains(&digit));
+    let mut res = String::from(if negative { "-" } else { "" });
+    res.push_str(prefix);
+    for (i, digit) in integer.chars().enumerate() {
+        if let Some(separator) = locale.group_separator {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                res.push(separator);
+            }
+        }
+        res.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        res.push(locale.decimal_mark);
+        res.push_str(fraction);
+    }
+    res
+}
+
+fn engineering(x: f64, places: usize, locale: &Locale) -> String {
+    let mut exponent = match x {
+        0.0 => 0,
+        _ => (x.abs().log10() / 3.0).floor() as i32 * 3,
+    };
+    let mut mantissa = x / 10f64.powi(exponent);
+    // Rounding may carry the mantissa up to 1000.
+    if format!("{:.*}", places, mantissa.abs()).parse::<f64>() == Ok(1000.0) {
+        exponent += 3;
+        mantissa /= 1000.0;
+    }
+    let plain = Locale {
+        group_separator: None,
+        ..*locale
+    };
+    format!("{}e{}", fixed(mantissa, places, &plain, ""), exponent)
+}
+
+impl<T: Value + Into<f64>> Graph<T> {
+    /// Renders the output of the node according to its format, values separated by `", "`.
+    /// Values of nodes without a format are rendered in full, sensitive outputs are redacted.
+    pub fn render(&mut self, node: NodeId, locale: &Locale) -> Result<String, GraphError> {
+        self.compute(node)?;
+        let inner = self.inner(node);
+        let output = inner.output().ok_or(GraphError::NotComputed(node))?;
+        if inner.sensitive {
+            return Ok(inner.display_value(output));
+        }
+        let values: Vec<String> = output
+            .iter()
+            .map(|x| {
+                let x: f64 = x.clone().into();
+                match &inner.format {
+                    Some(format) => format.apply(x, locale),
+                    None => x.to_string().replace('.', &locale.decimal_mark.to_string()),
+                }
+            })
+            .collect();
+        Ok(values.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let en = Locale::EN;
+        assert_eq!(Format::Decimal(2).apply(1234567.891, &en), "1,234,567.89");
+        assert_eq!(Format::Decimal(0).apply(-999.6, &en), "-1,000");
+        assert_eq!(Format::Decimal(2).apply(-0.001, &en), "0.00");
+        assert_eq!(Format::Decimal(2).apply(1234.5, &Locale::DE), "1.234,50");
+        assert_eq!(Format::Percent(1).apply(0.1234, &en), "12.3%");
+        let euro = Format::Currency {
+            symbol: "€".to_owned(),
+            places: 2,
+        };
+        assert_eq!(euro.apply(-1234.5, &en), "-€1,234.50");
+        assert_eq!(Format::Engineering(2).apply(12345.0, &en), "12.35e3");
+        assert_eq!(Format::Engineering(1).apply(0.0000015, &en), "1.5e-6");
+        assert_eq!(Format::Engineering(1).apply(999.96, &en), "1.0e3");
+        assert_eq!(Format::Engineering(0).apply(0.0, &en), "0e0");
+        assert_eq!(Format::Decimal(2).apply(f64::NAN, &en), "NaN");
+    }
+
+    #[test]
+    fn test_render() {
+        let mut graph = Graph::new();
+        let rate = graph
+            .node(|input| input)
+            .input(vec![0.05, 0.125])
+            .format(Format::Percent(1))
+            .build();
+        let secret = graph
+            .node(|input| input)
+            .input(vec![1.0])
+            .format(Format::Decimal(2))
+            .sensitive()
+            .build();
+        let raw = graph.node(|input| input).input(vec![1.5]).build();
+
+        assert_eq!(graph.render(rate, &Locale::EN).unwrap(), "5.0%, 12.5%");
+        assert_eq!(graph.render(secret, &Locale::EN).unwrap(), "<redacted>");
+        assert_eq!(graph.render(raw, &Locale::DE).unwrap(), "1,5");
+        assert_eq!(graph.format(rate), Some(&Format::Percent(1)));
+    }
+}
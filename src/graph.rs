@@ -0,0 +1,2915 @@
+use std::collections::{HashMap, VecDeque};
+use std::mem;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::SystemTime;
+
+use crate::context::EvalContext;
+use crate::delta::Change;
+use crate::error::{GraphError, NodeError};
+use crate::format::Format;
+use crate::history::History;
+use crate::inputs::Inputs;
+use crate::interner::Interner;
+use crate::node::{Bypass, NodeId, NodeInner};
+use crate::registry::OpRegistry;
+use crate::rng::SamplingMode;
+use crate::shadow::{Shadow, ShadowReport};
+use crate::value::Value;
+use crate::view::OutputView;
+
+const REFUSED_CAPACITY: usize = 64;
+
+/// Arena owning every node of a graph. Nodes and edges are addressed by [`NodeId`], so dropping
+/// the graph frees all of them and cloning it produces an independent copy. Node functions must
+/// be `Send + Sync`, so a graph can be built on one thread and evaluated on another.
+///
+/// Nodes exchange vectors of `T`. [`Graph::new`] builds the default `f32` graph, use
+/// `Graph::<T>::default()` for other value types.
+pub struct Graph<T = f32> {
+    nodes: Vec<NodeInner<T>>,
+    names: HashMap<String, NodeId>,
+    // Shared with clones of the graph.
+    interner: Option<Arc<Mutex<dyn Interner<T> + Send>>>,
+    // Timestamps recorded history entries.
+    clock: Arc<dyn Fn() -> SystemTime + Send + Sync>,
+    // Most recent refused requests, oldest first. Shared with clones of the graph.
+    refused: Arc<Mutex<VecDeque<(NodeId, String)>>>,
+    seed: u64,
+    // Evaluations started since the graph was seeded, numbers the random streams of the current
+    // one.
+    evaluations: u64,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T: Value> Default for Graph<T> {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            names: HashMap::new(),
+            interner: None,
+            clock: Arc::new(SystemTime::now),
+            refused: Arc::default(),
+            seed: 0,
+            evaluations: 0,
+        }
+    }
+}
+
+impl<T: Value> Clone for Graph<T> {
+    fn clone(&self) -> Self {
+        Self {
+            nodes: self.nodes.clone(),
+            names: self.names.clone(),
+            interner: self.interner.clone(),
+            clock: self.clock.clone(),
+            refused: self.refused.clone(),
+            seed: self.seed,
+            evaluations: self.evaluations,
+        }
+    }
+}
+
+impl<T: Value> Graph<T> {
+    /// `func` may be any closure, it receives the outputs of the children in insertion order
+    /// followed by the node's external input.
+    pub fn add_node<F>(&mut self, func: F) -> NodeId
+    where
+        F: Fn(Vec<T>) -> Vec<T> + Send + Sync + 'static,
+    {
+        self.node(func).build()
+    }
+
+    /// Like [`Graph::add_node`] for a node that can be looked up with [`Graph::node_by_name`].
+    pub fn add_node_named<F>(
+        &mut self,
+        name: impl Into<String>,
+        func: F,
+    ) -> Result<NodeId, GraphError>
+    where
+        F: Fn(Vec<T>) -> Vec<T> + Send + Sync + 'static,
+    {
+        Ok(self.node(func).name(name)?.build())
+    }
+
+    /// Like [`Graph::add_node`] for functions that can fail, their errors are returned from
+    /// [`Graph::compute`].
+    pub fn add_fallible_node<F>(&mut self, func: F) -> NodeId
+    where
+        F: Fn(Vec<T>) -> Result<Vec<T>, NodeError> + Send + Sync + 'static,
+    {
+        self.fallible_node(func).build()
+    }
+
+    /// Adds a node and returns a builder to configure and wire it.
+    pub fn node<F>(&mut self, func: F) -> NodeBuilder<'_, T>
+    where
+        F: Fn(Vec<T>) -> Vec<T> + Send + Sync + 'static,
+    {
+        self.fallible_node(move |input| Ok(func(input)))
+    }
+
+    pub fn fallible_node<F>(&mut self, func: F) -> NodeBuilder<'_, T>
+    where
+        F: Fn(Vec<T>) -> Result<Vec<T>, NodeError> + Send + Sync + 'static,
+    {
+        self.contextual_node(move |input, _| func(input))
+    }
+
+    /// Adds a node whose function can also read the outputs of other computed nodes through an
+    /// [`EvalContext`], e.g. to join values across branches that are not wired as children.
+    /// Such reads are not dependencies, changing the nodes read does not invalidate this one.
+    pub fn contextual_node<F>(&mut self, func: F) -> NodeBuilder<'_, T>
+    where
+        F: Fn(Vec<T>, &EvalContext<T>) -> Result<Vec<T>, NodeError> + Send + Sync + 'static,
+    {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(NodeInner::new(Some(Arc::new(func))));
+        NodeBuilder { graph: self, id }
+    }
+
+    /// Adds a node whose children are connected to the named `ports`, see
+    /// [`Graph::connect_port`]. Its function receives the output of each port separately instead
+    /// of a flattened input, children added without a port are not passed to it.
+    pub fn port_node<F>(&mut self, ports: &[&str], func: F) -> NodeBuilder<'_, T>
+    where
+        F: Fn(&Inputs<T>) -> Result<Vec<T>, NodeError> + Send + Sync + 'static,
+    {
+        let id = NodeId(self.nodes.len());
+        let mut inner = NodeInner::new(None);
+        inner.port_func = Some(Arc::new(func));
+        inner.ports = ports.iter().map(|port| (port.to_string(), None)).collect();
+        self.nodes.push(inner);
+        NodeBuilder { graph: self, id }
+    }
+
+    /// Connects `child` to `port` of `node`, replacing the child connected to it before.
+    pub fn connect_port(
+        &mut self,
+        node: NodeId,
+        port: &str,
+        child: NodeId,
+    ) -> Result<(), GraphError> {
+        let index = self
+            .get(node)?
+            .ports
+            .iter()
+            .position(|(name, _)| name == port)
+            .ok_or_else(|| GraphError::UnknownPort {
+                node,
+                port: port.to_owned(),
+            })?;
+        // The child bound to the port is about to be dropped, it does not count against the arity.
+        let rebinding = self.nodes[node.0].ports[index].1.is_some();
+        self.add_edge(node, child, rebinding)?;
+        if let Some(previous) = self.nodes[node.0].ports[index].1.replace(child) {
+            self.remove_edge(node, previous)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a node running the op registered under `key`, described like the op. Only such nodes
+    /// can be serialized.
+    pub fn registered_node(
+        &mut self,
+        registry: &OpRegistry<T>,
+        key: &str,
+    ) -> Result<NodeBuilder<'_, T>, GraphError> {
+        let func = registry
+            .get(key)
+            .ok_or_else(|| GraphError::UnknownOp(key.to_owned()))?;
+        let id = NodeId(self.nodes.len());
+        let mut inner = NodeInner::new(Some(func.clone()));
+        inner.op = Some(key.to_owned());
+        inner.description = registry.description(key).map(str::to_owned);
+        self.nodes.push(inner);
+        Ok(NodeBuilder { graph: self, id })
+    }
+
+    /// Adds a node for an op that may not be registered yet. Computing it fails until the op is
+    /// bound by [`Graph::resolve_ops`].
+    pub fn placeholder_node(&mut self, key: impl Into<String>) -> NodeBuilder<'_, T> {
+        let id = NodeId(self.nodes.len());
+        let mut inner = NodeInner::new(None);
+        inner.op = Some(key.into());
+        self.nodes.push(inner);
+        NodeBuilder { graph: self, id }
+    }
+
+    /// Binds every placeholder to its op in `registry`. Nothing is bound if any of them is
+    /// missing from the registry, the error names the first such node.
+    pub fn resolve_ops(&mut self, registry: &OpRegistry<T>) -> Result<(), GraphError> {
+        let mut resolved = vec![];
+        for (index, inner) in self.nodes.iter().enumerate() {
+            if let (None, Some(op)) = (&inner.func, &inner.op) {
+                let func = registry.get(op).ok_or_else(|| GraphError::UnresolvedOp {
+                    node: NodeId(index),
+                    op: op.clone(),
+                })?;
+                resolved.push((index, func.clone()));
+            }
+        }
+        for (index, func) in resolved {
+            let inner = &mut self.nodes[index];
+            inner.func = Some(func);
+            if inner.description.is_none() {
+                inner.description = inner
+                    .op
+                    .as_deref()
+                    .and_then(|op| registry.description(op))
+                    .map(str::to_owned);
+            }
+        }
+        Ok(())
+    }
+
+    /// Ids of the nodes that were not removed, in creation order.
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.nodes.len())
+            .filter(|index| !self.nodes[*index].removed)
+            .map(NodeId)
+    }
+
+    /// Every node, children before their parents. Roots of the traversal are taken in id order,
+    /// so the order only changes with the graph.
+    pub fn iter_topo(&self) -> impl Iterator<Item = NodeId> {
+        let mut order = vec![];
+        let mut visited = vec![false; self.nodes.len()];
+        for node in self.nodes() {
+            self.post_order(node, &mut visited, &mut order, |_| false);
+        }
+        order.into_iter()
+    }
+
+    /// Nodes that consume the output of `node` directly or indirectly, nearest first, in the
+    /// order the edges were added. Empty for unknown nodes.
+    pub fn ancestors(&self, node: NodeId) -> impl Iterator<Item = NodeId> {
+        self.reachable(node, |inner| &inner.up)
+    }
+
+    /// Nodes whose outputs `node` depends on directly or indirectly, nearest first, in the order
+    /// the edges were added. Empty for unknown nodes.
+    pub fn descendants(&self, node: NodeId) -> impl Iterator<Item = NodeId> {
+        self.reachable(node, |inner| &inner.down)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Disconnects one `child` edge of `parent`.
+    pub fn remove_child(&mut self, parent: NodeId, child: NodeId) -> Result<(), GraphError> {
+        self.remove_edge(parent, child)?;
+        self.disconnect_port(parent, child);
+        Ok(())
+    }
+
+    /// Deletes the node together with its edges, its parents are left with fewer inputs. The ids
+    /// of the other nodes stay valid, the removed id is rejected from then on.
+    pub fn remove_node(&mut self, node: NodeId) -> Result<(), GraphError> {
+        self.get(node)?;
+        self.invalidate(node);
+        let inner = &mut self.nodes[node.0];
+        let (up, down) = (mem::take(&mut inner.up), mem::take(&mut inner.down));
+        if let Some(name) = inner.name.take() {
+            self.names.remove(&name);
+        }
+        self.nodes[node.0] = NodeInner::new(None);
+        self.nodes[node.0].removed = true;
+        for inner in self.nodes.iter_mut() {
+            if inner
+                .shadow
+                .as_ref()
+                .is_some_and(|shadow| shadow.candidate == node)
+            {
+                inner.shadow = None;
+            }
+        }
+        for child in down {
+            self.nodes[child.0].up.retain(|parent| *parent != node);
+        }
+        for parent in up {
+            self.nodes[parent.0].down.retain(|child| *child != node);
+            while self.disconnect_port(parent, node) {}
+            self.update_volatile(parent);
+        }
+        Ok(())
+    }
+
+    /// Swaps the function of the node while keeping its wiring and settings. The node is no
+    /// longer tied to a registered op.
+    pub fn replace_func<F>(&mut self, node: NodeId, func: F) -> Result<(), GraphError>
+    where
+        F: Fn(Vec<T>) -> Vec<T> + Send + Sync + 'static,
+    {
+        self.replace_fallible_func(node, move |input| Ok(func(input)))
+    }
+
+    pub fn replace_fallible_func<F>(&mut self, node: NodeId, func: F) -> Result<(), GraphError>
+    where
+        F: Fn(Vec<T>) -> Result<Vec<T>, NodeError> + Send + Sync + 'static,
+    {
+        self.get(node)?;
+        self.invalidate(node);
+        let inner = &mut self.nodes[node.0];
+        inner.func = Some(Arc::new(move |input, _: &EvalContext<T>| func(input)));
+        inner.update = None;
+        inner.op = None;
+        Ok(())
+    }
+
+    /// Lets the node update its previous output when only some elements of its input changed,
+    /// instead of running its function on the whole input. `update` receives the output to patch
+    /// and the changes to the flattened input, and returns the changes it made to the output so
+    /// that incremental parents can be updated in turn.
+    ///
+    /// Changes originate from [`Input::update`] and travel up through incremental nodes. Any
+    /// other edit, or a child recomputed in full, makes the node run its function again.
+    pub fn set_incremental<F>(&mut self, node: NodeId, update: F) -> Result<(), GraphError>
+    where
+        F: Fn(&mut Vec<T>, &[Change<T>]) -> Result<Vec<Change<T>>, NodeError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.get(node)?;
+        let inner = &mut self.nodes[node.0];
+        inner.update = Some(Arc::new(update));
+        inner.pending = None;
+        Ok(())
+    }
+
+    /// Names the node, replacing its previous name. Names are unique within a graph.
+    pub fn set_name(&mut self, node: NodeId, name: impl Into<String>) -> Result<(), GraphError> {
+        let name = name.into();
+        match self.names.get(&name) {
+            Some(named) if *named == node => return Ok(()),
+            Some(_) => return Err(GraphError::DuplicateName(name)),
+            None => {}
+        }
+        self.get(node)?;
+        if let Some(previous) = self.nodes[node.0].name.replace(name.clone()) {
+            self.names.remove(&previous);
+        }
+        self.names.insert(name, node);
+        Ok(())
+    }
+
+    pub fn name(&self, node: NodeId) -> Option<&str> {
+        self.nodes.get(node.0)?.name.as_deref()
+    }
+
+    pub fn node_by_name(&self, name: &str) -> Option<NodeId> {
+        self.names.get(name).copied()
+    }
+
+    /// Describes what the node computes, the description is carried into serialized graphs,
+    /// [`Graph::node_info`] and exports.
+    pub fn set_description(
+        &mut self,
+        node: NodeId,
+        description: impl Into<String>,
+    ) -> Result<(), GraphError> {
+        self.get(node)?;
+        self.nodes[node.0].description = Some(description.into());
+        Ok(())
+    }
+
+    pub fn description(&self, node: NodeId) -> Option<&str> {
+        self.nodes.get(node.0)?.description.as_deref()
+    }
+
+    /// Sets how [`Graph::render`] renders the output of the node.
+    pub fn set_format(&mut self, node: NodeId, format: Format) -> Result<(), GraphError> {
+        self.get(node)?;
+        self.nodes[node.0].format = Some(format);
+        Ok(())
+    }
+
+    pub fn format(&self, node: NodeId) -> Option<&Format> {
+        self.nodes.get(node.0)?.format.as_ref()
+    }
+
+    /// Registry key of the node's op, see [`Graph::registered_node`].
+    pub fn op(&self, node: NodeId) -> Option<&str> {
+        self.nodes.get(node.0)?.op.as_deref()
+    }
+
+    /// Routes every newly computed output through `interner`.
+    pub fn set_interner<I: Interner<T> + Send + 'static>(&mut self, interner: I) {
+        self.interner = Some(Arc::new(Mutex::new(interner)));
+    }
+
+    /// Replaces the system clock used to timestamp history entries.
+    pub fn set_clock<C: Fn() -> SystemTime + Send + Sync + 'static>(&mut self, clock: C) {
+        self.clock = Arc::new(clock);
+    }
+
+    /// Seeds the random streams handed to node functions, see [`EvalContext::rng`], and restarts
+    /// the count of evaluations they are keyed by. Graphs seeded alike draw the same values in
+    /// the same evaluations, whether they are computed sequentially or in parallel.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.evaluations = 0;
+    }
+
+    /// Sets how the node draws values from [`EvalContext::sampler`]. Antithetic and quasi-random
+    /// modes pair or spread the values of consecutive evaluations, see [`SamplingMode`].
+    pub fn set_sampling(&mut self, node: NodeId, mode: SamplingMode) -> Result<(), GraphError> {
+        self.get(node)?;
+        self.nodes[node.0].sampling = mode;
+        Ok(())
+    }
+
+    /// Keeps the last `capacity` outputs computed for the node and the last `capacity` values
+    /// of its external input, see [`Graph::history`] and [`Graph::compute_as_of`]. A capacity
+    /// of zero stops recording and drops the entries kept so far.
+    pub fn set_history(&mut self, node: NodeId, capacity: usize) -> Result<(), GraphError> {
+        self.get(node)?;
+        let inner = &mut self.nodes[node.0];
+        inner.history = match capacity {
+            0 => None,
+            _ => {
+                let mut history = History::new(capacity);
+                history.push_input((self.clock)(), inner.input.as_deref());
+                Some(history)
+            }
+        };
+        Ok(())
+    }
+
+    /// Evaluates `candidate` whenever `node` is evaluated, comparing their outputs with
+    /// `distance`, until it is promoted or cleared. Failures of the candidate are recorded in the
+    /// report, not returned. Parallel evaluation does not run shadows.
+    pub fn set_shadow<F>(
+        &mut self,
+        node: NodeId,
+        candidate: NodeId,
+        distance: F,
+    ) -> Result<(), GraphError>
+    where
+        F: Fn(&[T], &[T]) -> f64 + Send + Sync + 'static,
+    {
+        self.get(node)?;
+        self.get(candidate)?;
+        if node == candidate {
+            return Err(GraphError::CycleDetected(vec![node, node]));
+        }
+        self.nodes[node.0].shadow = Some(Shadow {
+            candidate,
+            distance: Arc::new(distance),
+            report: ShadowReport::default(),
+        });
+        Ok(())
+    }
+
+    pub fn shadow_report(&self, node: NodeId) -> Option<&ShadowReport> {
+        Some(&self.nodes.get(node.0)?.shadow.as_ref()?.report)
+    }
+
+    /// Stops shadowing the node, returning the final report.
+    pub fn clear_shadow(&mut self, node: NodeId) -> Option<ShadowReport> {
+        let shadow = self.nodes.get_mut(node.0)?.shadow.take()?;
+        Some(shadow.report)
+    }
+
+    /// Switches the parents of the node over to its shadow candidate, in the same input
+    /// positions and ports. The node itself is left in place without parents. Fails without
+    /// changing anything if a parent is below the candidate.
+    pub fn promote_shadow(&mut self, node: NodeId) -> Result<NodeId, GraphError> {
+        let candidate = self
+            .get(node)?
+            .shadow
+            .as_ref()
+            .ok_or(GraphError::NoShadow(node))?
+            .candidate;
+        let parents = self.nodes[node.0].up.clone();
+        for parent in parents.iter() {
+            if let Some(path) = self.path_up(*parent, candidate) {
+                let mut cycle = vec![*parent];
+                cycle.extend(path.into_iter().rev());
+                return Err(GraphError::CycleDetected(cycle));
+            }
+        }
+
+        self.nodes[node.0].shadow = None;
+        self.nodes[node.0].up.clear();
+        for parent in parents.iter() {
+            let inner = &mut self.nodes[parent.0];
+            for child in inner.down.iter_mut().filter(|child| **child == node) {
+                *child = candidate;
+            }
+            for (_, port) in inner
+                .ports
+                .iter_mut()
+                .filter(|(_, port)| *port == Some(node))
+            {
+                *port = Some(candidate);
+            }
+        }
+        self.nodes[candidate.0].up.extend(parents.iter().copied());
+        for parent in parents {
+            self.update_volatile(parent);
+            self.invalidate(parent);
+        }
+        Ok(candidate)
+    }
+
+    /// Recorded outputs of the node with the time they were computed, oldest first. Restricted
+    /// like [`Graph::compute_with`].
+    pub fn history(
+        &self,
+        node: NodeId,
+        capabilities: &[&str],
+    ) -> Result<impl Iterator<Item = (SystemTime, &[T])>, GraphError> {
+        self.authorize(node, capabilities)?;
+        Ok(self.nodes[node.0].history.iter().flat_map(History::outputs))
+    }
+
+    /// Computes `node` from the external inputs that were current at `time`, leaving the graph
+    /// untouched. Every node below it with an input must record history covering that time.
+    /// Impure nodes still run with today's state.
+    pub fn compute_as_of(&self, node: NodeId, time: SystemTime) -> Result<Vec<T>, GraphError> {
+        self.get(node)?;
+        let mut past = self.clone();
+        // Past outputs are dropped with `past`, there is no point sharing them.
+        past.interner = None;
+        let mut visited = vec![false; self.nodes.len()];
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            if mem::replace(&mut visited[node.0], true) {
+                continue;
+            }
+            let inner = &self.nodes[node.0];
+            stack.extend_from_slice(&inner.down);
+            let input = match inner.history {
+                Some(ref history) => history.input_at(time),
+                None if inner.input.is_none() => continue,
+                None => None,
+            };
+            let input = input.ok_or(GraphError::NoHistory(node))?;
+            past.nodes[node.0].input = input.map(<[T]>::to_vec);
+            past.invalidate(node);
+        }
+        past.compute(node).map(<[T]>::to_vec)
+    }
+
+    pub fn input(&mut self, node: NodeId) -> Result<Input<'_, T>, GraphError> {
+        self.get(node)?;
+        Ok(Input { graph: self, node })
+    }
+
+    /// Makes `child` an input of `parent`. Fails without changing anything when the connection
+    /// would close a cycle or exceed the arity of `parent`.
+    pub fn add_children(&mut self, parent: NodeId, child: NodeId) -> Result<(), GraphError> {
+        self.add_edge(parent, child, false)
+    }
+
+    // Like `add_children`, leaving room for one more child than the arity when `replacing`.
+    fn add_edge(
+        &mut self,
+        parent: NodeId,
+        child: NodeId,
+        replacing: bool,
+    ) -> Result<(), GraphError> {
+        let inner = self.get(parent)?;
+        self.get(child)?;
+        if let Some(arity) = inner.arity {
+            if inner.down.len() - usize::from(replacing) >= arity {
+                return Err(GraphError::ArityExceeded {
+                    node: parent,
+                    arity,
+                });
+            }
+        }
+        if let Some(path) = self.path_up(parent, child) {
+            let mut cycle = vec![parent];
+            cycle.extend(path.into_iter().rev());
+            return Err(GraphError::CycleDetected(cycle));
+        }
+
+        self.nodes[parent.0].down.push(child);
+        self.nodes[child.0].up.push(parent);
+
+        self.update_volatile(parent);
+        self.invalidate(parent);
+        Ok(())
+    }
+
+    /// Feeds the output of `src` into `dst`, the same as `add_children(dst, src)` named in the
+    /// direction data flows.
+    pub fn connect(&mut self, src: NodeId, dst: NodeId) -> Result<(), GraphError> {
+        self.add_children(dst, src)
+    }
+
+    /// Declares how many children the node expects. Connecting more fails and
+    /// [`Graph::validate`] reports nodes with fewer. Nodes with ports expect one child per port
+    /// instead.
+    pub fn set_arity(&mut self, node: NodeId, arity: usize) -> Result<(), GraphError> {
+        self.get(node)?;
+        self.nodes[node.0].arity = Some(arity);
+        Ok(())
+    }
+
+    /// Checks the wiring of every node before computing, returning all problems found: nodes
+    /// with fewer children than their arity and unconnected ports.
+    pub fn validate(&self) -> Result<(), Vec<GraphError>> {
+        let mut errors = vec![];
+        for node in self.nodes() {
+            let inner = &self.nodes[node.0];
+            if let Some(expected) = inner.arity {
+                if inner.down.len() < expected {
+                    errors.push(GraphError::UnderConnected {
+                        node,
+                        expected,
+                        actual: inner.down.len(),
+                    });
+                }
+            }
+            for (port, child) in inner.ports.iter() {
+                if child.is_none() {
+                    errors.push(GraphError::MissingPort {
+                        node,
+                        port: port.clone(),
+                    });
+                }
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    /// Bypasses the function of the node, which passes its flattened input through instead until
+    /// it is enabled again. The node keeps its place in the graph and its settings.
+    pub fn disable(&mut self, node: NodeId) -> Result<(), GraphError> {
+        self.get(node)?;
+        self.nodes[node.0].disabled = Some(Bypass::Identity);
+        self.invalidate(node);
+        Ok(())
+    }
+
+    /// Like [`Graph::disable`], with the node producing `fallback` instead.
+    pub fn disable_with(&mut self, node: NodeId, fallback: Vec<T>) -> Result<(), GraphError> {
+        self.get(node)?;
+        self.nodes[node.0].disabled = Some(Bypass::Fallback(fallback));
+        self.invalidate(node);
+        Ok(())
+    }
+
+    pub fn enable(&mut self, node: NodeId) -> Result<(), GraphError> {
+        self.get(node)?;
+        if self.nodes[node.0].disabled.take().is_some() {
+            self.invalidate(node);
+        }
+        Ok(())
+    }
+
+    pub fn is_disabled(&self, node: NodeId) -> Result<bool, GraphError> {
+        Ok(self.get(node)?.disabled.is_some())
+    }
+
+    /// Lets the node's function receive its only child's buffer by value and mutate it in place.
+    /// The evaluator falls back to a copy unless that child has no other consumers and the node
+    /// has no external input. Children that record history or whose output was requested are
+    /// copied as well. A moved child has no output until it is computed again, so
+    /// [`EvalContext::output`] does not see it.
+    pub fn set_in_place(&mut self, node: NodeId, in_place: bool) -> Result<(), GraphError> {
+        self.get(node)?;
+        self.nodes[node.0].in_place = in_place;
+        Ok(())
+    }
+
+    /// Sensitive nodes never have their values written to diagnostics, they are redacted instead.
+    pub fn set_sensitive(&mut self, node: NodeId, sensitive: bool) -> Result<(), GraphError> {
+        self.get(node)?;
+        self.nodes[node.0].sensitive = sensitive;
+        Ok(())
+    }
+
+    /// Impure nodes (IO, RNG, ...) are never served from cache, they and every node depending on
+    /// them are recomputed on each evaluation.
+    pub fn set_pure(&mut self, node: NodeId, pure: bool) -> Result<(), GraphError> {
+        self.get(node)?;
+        self.nodes[node.0].pure = pure;
+        self.update_volatile(node);
+        Ok(())
+    }
+
+    /// Restricts requests for the output of `node` to evaluations holding `capability`, see
+    /// [`Graph::compute_with`]. Only the requested node is checked, restricted nodes still feed
+    /// their parents.
+    pub fn require_capability(
+        &mut self,
+        node: NodeId,
+        capability: impl Into<String>,
+    ) -> Result<(), GraphError> {
+        self.get(node)?;
+        self.nodes[node.0].capabilities.push(capability.into());
+        Ok(())
+    }
+
+    /// Computes the node, reusing cached results below it. On error no cache is filled for the
+    /// failing node or its ancestors.
+    ///
+    /// The output borrows the graph, so it cannot be held across a change to the graph:
+    ///
+    /// ```compile_fail
+    /// let mut graph = computation_graph::Graph::new();
+    /// let node = graph.node(|input| input).input(vec![1.0]).build();
+    /// let output = graph.compute(node).unwrap();
+    /// graph.input(node).unwrap().set(vec![2.0]);
+    /// assert_eq!(output, [1.0]);
+    /// ```
+    ///
+    /// Copy it out first instead:
+    ///
+    /// ```
+    /// let mut graph = computation_graph::Graph::new();
+    /// let node = graph.node(|input| input).input(vec![1.0]).build();
+    /// let output = graph.compute(node).unwrap().to_vec();
+    /// graph.input(node).unwrap().set(vec![2.0]);
+    /// assert_eq!(output, [1.0]);
+    /// assert_eq!(graph.compute(node).unwrap(), [2.0]);
+    /// ```
+    pub fn compute(&mut self, node: NodeId) -> Result<&[T], GraphError> {
+        self.compute_with(node, &[])
+    }
+
+    /// Like [`Graph::compute`] on behalf of a caller holding `capabilities`. Refused requests fail
+    /// with [`GraphError::Unauthorized`] and are recorded, see [`Graph::refused_attempts`].
+    pub fn compute_with(
+        &mut self,
+        node: NodeId,
+        capabilities: &[&str],
+    ) -> Result<&[T], GraphError> {
+        self.authorize(node, capabilities)?;
+        self.compute_nodes(&[node])?;
+        self.nodes[node.0]
+            .output()
+            .ok_or(GraphError::NotComputed(node))
+    }
+
+    pub fn view(&mut self, node: NodeId) -> Result<OutputView<'_, T>, GraphError> {
+        self.compute(node).map(OutputView::new)
+    }
+
+    /// Moves the computed output out of the node instead of cloning it, the node is left dirty
+    /// and will be recomputed on the next request.
+    pub fn take_output(&mut self, node: NodeId) -> Result<Vec<T>, GraphError> {
+        self.take_output_with(node, &[])
+    }
+
+    /// Like [`Graph::take_output`] on behalf of a caller holding `capabilities`, see
+    /// [`Graph::compute_with`].
+    pub fn take_output_with(
+        &mut self,
+        node: NodeId,
+        capabilities: &[&str],
+    ) -> Result<Vec<T>, GraphError> {
+        self.authorize(node, capabilities)?;
+        self.compute_nodes(&[node])?;
+        self.nodes[node.0]
+            .take_output()
+            .ok_or(GraphError::NotComputed(node))
+    }
+
+    /// Outputs of several nodes computed in a single evaluation, so nodes they share are
+    /// evaluated once and every output reflects the same inputs.
+    pub fn compute_many(&mut self, nodes: &[NodeId]) -> Result<Vec<Vec<T>>, GraphError> {
+        for node in nodes {
+            self.authorize(*node, &[])?;
+        }
+        self.compute_nodes(nodes)?;
+        nodes
+            .iter()
+            .map(|node| {
+                self.nodes[node.0]
+                    .output()
+                    .map(<[T]>::to_vec)
+                    .ok_or(GraphError::NotComputed(*node))
+            })
+            .collect()
+    }
+
+    /// Outputs of every named node without parents, computed in a single evaluation.
+    pub fn compute_all_named(&mut self) -> Result<HashMap<String, Vec<T>>, GraphError> {
+        let sinks: Vec<NodeId> = self
+            .nodes()
+            .filter(|node| self.nodes[node.0].up.is_empty() && self.nodes[node.0].name.is_some())
+            .collect();
+        let outputs = self.compute_many(&sinks)?;
+        Ok(sinks
+            .into_iter()
+            .map(|sink| self.nodes[sink.0].name.clone().unwrap_or_default())
+            .zip(outputs)
+            .collect())
+    }
+
+    /// Most recent requests refused for a missing capability, oldest first, as the requested node
+    /// and the first capability it lacked. Only the last 64 are kept.
+    pub fn refused_attempts(&self) -> Vec<(NodeId, String)> {
+        let refused = self.refused.lock().unwrap_or_else(PoisonError::into_inner);
+        refused.iter().cloned().collect()
+    }
+
+    /// Whether the node must be recomputed before its output can be served.
+    pub fn is_dirty(&self, node: NodeId) -> Result<bool, GraphError> {
+        self.get(node).map(NodeInner::is_dirty)
+    }
+
+    // Master seed and number of the current evaluation, see `EvalContext::rng`.
+    pub(crate) fn rng_key(&self) -> (u64, u64) {
+        (self.seed, self.evaluations)
+    }
+
+    #[cfg_attr(not(feature = "rayon"), allow(dead_code))]
+    pub(crate) fn advance_evaluations(&mut self, count: u64) {
+        self.evaluations += count;
+    }
+
+    pub(crate) fn inner(&self, node: NodeId) -> &NodeInner<T> {
+        &self.nodes[node.0]
+    }
+
+    // Installs outputs loaded from storage as up-to-date caches. Nodes without one are
+    // invalidated afterwards so that their ancestors are dirty too.
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) fn restore_caches(&mut self, caches: Vec<Option<Vec<T>>>) {
+        let mut missing = vec![];
+        for (index, cache) in caches.into_iter().enumerate() {
+            let inner = &mut self.nodes[index];
+            inner.dirty = false;
+            inner.cache = cache.map(Arc::new);
+            if inner.cache.is_none() {
+                missing.push(NodeId(index));
+            }
+        }
+        for node in missing {
+            self.invalidate(node);
+        }
+    }
+
+    fn remove_edge(&mut self, parent: NodeId, child: NodeId) -> Result<(), GraphError> {
+        self.get(child)?;
+        let position = self
+            .get(parent)?
+            .down
+            .iter()
+            .position(|node| *node == child)
+            .ok_or(GraphError::NoEdge { parent, child })?;
+        self.invalidate(parent);
+        self.nodes[parent.0].down.remove(position);
+        let up = &mut self.nodes[child.0].up;
+        if let Some(position) = up.iter().position(|node| *node == parent) {
+            up.remove(position);
+        }
+        self.update_volatile(parent);
+        Ok(())
+    }
+
+    // Clears one port of `parent` connected to `child`, if any.
+    fn disconnect_port(&mut self, parent: NodeId, child: NodeId) -> bool {
+        let ports = &mut self.nodes[parent.0].ports;
+        match ports.iter_mut().find(|(_, port)| *port == Some(child)) {
+            Some((_, port)) => {
+                *port = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn get(&self, node: NodeId) -> Result<&NodeInner<T>, GraphError> {
+        self.nodes
+            .get(node.0)
+            .filter(|inner| !inner.removed)
+            .ok_or(GraphError::UnknownNode(node))
+    }
+
+    pub(crate) fn authorize(&self, node: NodeId, capabilities: &[&str]) -> Result<(), GraphError> {
+        let required = &self.get(node)?.capabilities;
+        let Some(capability) = required
+            .iter()
+            .find(|c| !capabilities.contains(&c.as_str()))
+        else {
+            return Ok(());
+        };
+        let mut refused = self.refused.lock().unwrap_or_else(PoisonError::into_inner);
+        if refused.len() == REFUSED_CAPACITY {
+            refused.pop_front();
+        }
+        refused.push_back((node, capability.clone()));
+        Err(GraphError::Unauthorized {
+            node,
+            capability: capability.clone(),
+        })
+    }
+
+    // Evaluates every dirty node below `nodes` exactly once, children before parents, so nodes
+    // shared by several parents are neither computed nor invalidated twice. The outputs of
+    // `nodes` are kept even when a parent could otherwise take them in place.
+    pub(crate) fn compute_nodes(&mut self, nodes: &[NodeId]) -> Result<(), GraphError> {
+        self.evaluations += 1;
+        let order = self.evaluation_order(nodes);
+        for node in order.iter() {
+            self.evaluate(*node, nodes)?;
+        }
+        // Candidates run last so that nodes they share with the requested ones are up to date.
+        for node in order {
+            if self.nodes[node.0].shadow.is_some() {
+                self.run_shadow(node, nodes);
+            }
+        }
+        Ok(())
+    }
+
+    // Evaluates the candidate shadowing `node` and records how their outputs compare.
+    // The outputs of `requested` are kept like in the evaluation the shadow runs alongside.
+    fn run_shadow(&mut self, node: NodeId, requested: &[NodeId]) {
+        let Some(shadow) = self.nodes[node.0].shadow.as_ref() else {
+            return;
+        };
+        let (candidate, distance) = (shadow.candidate, shadow.distance.clone());
+        let mut kept = requested.to_vec();
+        kept.extend([node, candidate]);
+        let result = self
+            .evaluation_order(&[candidate])
+            .into_iter()
+            .try_for_each(|id| self.evaluate(id, &kept))
+            .and_then(|()| {
+                let output = self.nodes[node.0]
+                    .output()
+                    .ok_or(GraphError::NotComputed(node))?;
+                let shadowed = self.nodes[candidate.0]
+                    .output()
+                    .ok_or(GraphError::NotComputed(candidate))?;
+                Ok(distance(output, shadowed))
+            });
+        if let Some(shadow) = self.nodes[node.0].shadow.as_mut() {
+            shadow.report.record(result);
+        }
+    }
+
+    // Patches the stale output when the node is incremental and its input changes are known,
+    // otherwise runs its function on the full input. `requested` as in `gather_input`.
+    fn evaluate(&mut self, node: NodeId, requested: &[NodeId]) -> Result<(), GraphError> {
+        let inner = &mut self.nodes[node.0];
+        let incremental = match (&inner.update, inner.volatile, inner.cache.is_some()) {
+            (Some(update), false, true) if inner.disabled.is_none() => inner
+                .pending
+                .take()
+                .map(|changes| (update.clone(), changes)),
+            _ => None,
+        };
+        let Some((update, changes)) = incremental else {
+            let input = self.gather_input(node, requested)?;
+            let output = self.run(node, input)?;
+            self.store_output(node, output, None);
+            return Ok(());
+        };
+        let mut output = inner.take_output().ok_or(GraphError::NotComputed(node))?;
+        let delta =
+            update(&mut output, &changes).map_err(|error| GraphError::from_node(node, error))?;
+        self.store_output(node, output, Some(delta));
+        Ok(())
+    }
+
+    pub(crate) fn evaluation_order(&self, nodes: &[NodeId]) -> Vec<NodeId> {
+        let mut order = vec![];
+        let mut visited = vec![false; self.nodes.len()];
+        for node in nodes {
+            self.collect_dirty(*node, &mut visited, &mut order);
+        }
+        order
+    }
+
+    // Post-order of the nodes below `node` that need evaluation. Clean cached nodes are leaves of
+    // the traversal since nothing below them has changed.
+    fn collect_dirty(&self, node: NodeId, visited: &mut [bool], order: &mut Vec<NodeId>) {
+        self.post_order(node, visited, order, |inner| {
+            !inner.dirty && !inner.volatile && inner.cache.is_some()
+        });
+    }
+
+    // Post-order of the nodes below `node`, skipping those for which `skip` holds along with
+    // everything only reachable through them. Uses an explicit stack so that the depth of the
+    // graph is not limited by the call stack.
+    fn post_order<F>(&self, node: NodeId, visited: &mut [bool], order: &mut Vec<NodeId>, skip: F)
+    where
+        F: Fn(&NodeInner<T>) -> bool,
+    {
+        let mut stack = vec![(node, false)];
+        while let Some((node, expanded)) = stack.pop() {
+            if expanded {
+                order.push(node);
+                continue;
+            }
+            if mem::replace(&mut visited[node.0], true) {
+                continue;
+            }
+            let inner = &self.nodes[node.0];
+            if skip(inner) {
+                continue;
+            }
+            stack.push((node, true));
+            stack.extend(inner.down.iter().rev().map(|child| (*child, false)));
+        }
+    }
+
+    // Assembles the function input of a node whose children all hold a result. The outputs of
+    // `requested` nodes are never moved into it.
+    pub(crate) fn gather_input(
+        &mut self,
+        node: NodeId,
+        requested: &[NodeId],
+    ) -> Result<Vec<T>, GraphError> {
+        let inner = &self.nodes[node.0];
+        if inner.port_func.is_some() && inner.disabled.is_none() {
+            // Ports are read from the children's outputs directly in `run`.
+            return Ok(vec![]);
+        }
+        if self.can_run_in_place(node, requested) {
+            // The only consumer of the child's buffer is this node, so it is moved instead of copied.
+            let child = self.nodes[node.0].down[0];
+            return self.nodes[child.0]
+                .take_output()
+                .ok_or(GraphError::NotComputed(child));
+        }
+        let inner = &self.nodes[node.0];
+        let mut input = vec![];
+        for child in inner.down.iter() {
+            let output = self.nodes[child.0]
+                .output()
+                .ok_or(GraphError::NotComputed(*child))?;
+            input.extend_from_slice(output);
+        }
+        input.extend(inner.input.iter().flatten().cloned());
+        Ok(input)
+    }
+
+    pub(crate) fn run(&self, node: NodeId, input: Vec<T>) -> Result<Vec<T>, GraphError> {
+        let inner = &self.nodes[node.0];
+        match inner.disabled {
+            Some(Bypass::Identity) => return Ok(input),
+            Some(Bypass::Fallback(ref fallback)) => return Ok(fallback.clone()),
+            None => {}
+        }
+        if let Some(ref port_func) = inner.port_func {
+            let mut ports = vec![];
+            for (name, child) in inner.ports.iter() {
+                let output = match child {
+                    Some(child) => Some(
+                        self.nodes[child.0]
+                            .output()
+                            .ok_or(GraphError::NotComputed(*child))?,
+                    ),
+                    None => None,
+                };
+                ports.push((name.as_str(), output));
+            }
+            let inputs = Inputs {
+                ports,
+                input: inner.input.as_deref(),
+            };
+            return port_func(&inputs).map_err(|error| GraphError::from_node(node, error));
+        }
+        let func = inner
+            .func
+            .as_ref()
+            .ok_or_else(|| GraphError::UnresolvedOp {
+                node,
+                op: inner.op.clone().unwrap_or_default(),
+            })?;
+        let context = EvalContext::new(self, node);
+        func(input, &context).map_err(|error| GraphError::from_node(node, error))
+    }
+
+    // `delta` lists the changes to the previous output, `None` if it was recomputed in full.
+    pub(crate) fn store_output(
+        &mut self,
+        node: NodeId,
+        output: Vec<T>,
+        delta: Option<Vec<Change<T>>>,
+    ) {
+        let output = match self.interner {
+            Some(ref interner) => interner
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .intern(output),
+            None => Arc::new(output),
+        };
+        let inner = &mut self.nodes[node.0];
+        if let Some(ref mut history) = inner.history {
+            history.push_output((self.clock)(), output.clone());
+        }
+        inner.cache = Some(output);
+        inner.dirty = false;
+        inner.pending = Some(vec![]);
+
+        for index in 0..self.nodes[node.0].up.len() {
+            let parent = self.nodes[node.0].up[index];
+            let offsets = match (&delta, &self.nodes[parent.0].update) {
+                (Some(_), Some(_)) => self.offsets(parent, node),
+                _ => None,
+            };
+            let pending = &mut self.nodes[parent.0].pending;
+            match (offsets, &delta, pending.as_mut()) {
+                (Some(offsets), Some(delta), Some(pending)) => {
+                    for offset in offsets {
+                        pending.extend(delta.iter().map(|change| Change {
+                            index: change.index + offset,
+                            ..change.clone()
+                        }));
+                    }
+                }
+                _ => *pending = None,
+            }
+        }
+    }
+
+    // Positions at which the output of `child` starts in the flattened input of `parent`, `None`
+    // if an output before it is not available.
+    fn offsets(&self, parent: NodeId, child: NodeId) -> Option<Vec<usize>> {
+        let mut offsets = vec![];
+        let mut offset = 0;
+        for node in self.nodes[parent.0].down.iter() {
+            if *node == child {
+                offsets.push(offset);
+            }
+            offset += self.nodes[node.0].output()?.len();
+        }
+        Some(offsets)
+    }
+
+    fn can_run_in_place(&self, node: NodeId, requested: &[NodeId]) -> bool {
+        let inner = &self.nodes[node.0];
+        inner.in_place
+            && inner.input.is_none()
+            && inner.down.len() == 1
+            && self.nodes[inner.down[0].0].up.len() == 1
+            // The caller reads the outputs it requested once the evaluation is done.
+            && !requested.contains(&inner.down[0])
+            // History shares the buffer, moving it would clear the cache without saving a copy.
+            && self.nodes[inner.down[0].0].history.is_none()
+            // The output of a shadowed node is still needed for the comparison.
+            && self.nodes[inner.down[0].0].shadow.is_none()
+    }
+
+    fn update_volatile(&mut self, node: NodeId) {
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            let inner = &self.nodes[node.0];
+            let volatile =
+                !inner.pure || inner.down.iter().any(|child| self.nodes[child.0].volatile);
+            if volatile != inner.volatile {
+                self.nodes[node.0].volatile = volatile;
+                stack.extend_from_slice(&self.nodes[node.0].up);
+            }
+        }
+    }
+
+    // `change` is set when a single element of the input was replaced.
+    fn input_changed(&mut self, node: NodeId, change: Option<Change<T>>) {
+        let inner = &mut self.nodes[node.0];
+        if let Some(ref mut history) = inner.history {
+            history.push_input((self.clock)(), inner.input.as_deref());
+        }
+        let Some(mut change) = change else {
+            self.invalidate(node);
+            return;
+        };
+        // External input values follow the outputs of all children.
+        let offset = self.nodes[node.0]
+            .down
+            .iter()
+            .map(|child| self.nodes[child.0].output().map(<[T]>::len))
+            .sum::<Option<usize>>();
+        let inner = &mut self.nodes[node.0];
+        match (offset, inner.update.is_some(), inner.pending.as_mut()) {
+            (Some(offset), true, Some(pending)) => {
+                change.index += offset;
+                pending.push(change);
+            }
+            _ => inner.pending = None,
+        }
+        self.mark_dirty(node);
+    }
+
+    // Marks the node and its ancestors dirty after an edit whose effect on its input is unknown.
+    fn invalidate(&mut self, node: NodeId) {
+        self.nodes[node.0].pending = None;
+        self.mark_dirty(node);
+    }
+
+    // Ancestors of a dirty node are already dirty, so repeated invalidations stop at the first
+    // one. Incremental nodes keep their stale output to update it later.
+    fn mark_dirty(&mut self, node: NodeId) {
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            let inner = &mut self.nodes[node.0];
+            if !mem::replace(&mut inner.dirty, true) {
+                if inner.update.is_none() {
+                    inner.cache = None;
+                }
+                stack.extend_from_slice(&inner.up);
+            }
+        }
+    }
+
+    // Nodes reached breadth-first from `node` along `edges`, excluding `node` itself. Each is
+    // listed once, at its first visit.
+    fn reachable<F>(&self, node: NodeId, edges: F) -> std::vec::IntoIter<NodeId>
+    where
+        F: Fn(&NodeInner<T>) -> &[NodeId],
+    {
+        let mut found = vec![];
+        let Ok(inner) = self.get(node) else {
+            return found.into_iter();
+        };
+        let mut visited = vec![false; self.nodes.len()];
+        visited[node.0] = true;
+        let mut queue = VecDeque::from_iter(edges(inner).iter().copied());
+        while let Some(next) = queue.pop_front() {
+            if mem::replace(&mut visited[next.0], true) {
+                continue;
+            }
+            found.push(next);
+            queue.extend(edges(&self.nodes[next.0]).iter().copied());
+        }
+        found.into_iter()
+    }
+
+    // Nodes from `from` up to `target` following parent links, both ends included. Searching
+    // upwards keeps wiring a fresh parent cheap however deep the graph below it is.
+    fn path_up(&self, from: NodeId, target: NodeId) -> Option<Vec<NodeId>> {
+        let mut previous = HashMap::new();
+        let mut stack = vec![(from, from)];
+        while let Some((node, before)) = stack.pop() {
+            if previous.contains_key(&node) {
+                continue;
+            }
+            previous.insert(node, before);
+            if node == target {
+                let mut path = vec![node];
+                let mut node = node;
+                while node != from {
+                    node = previous[&node];
+                    path.push(node);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            stack.extend(
+                self.nodes[node.0]
+                    .up
+                    .iter()
+                    .rev()
+                    .map(|parent| (*parent, node)),
+            );
+        }
+        None
+    }
+}
+
+/// Access to the external input of a node, see [`Graph::input`].
+pub struct Input<'a, T = f32> {
+    graph: &'a mut Graph<T>,
+    node: NodeId,
+}
+
+impl<T: Value> Input<'_, T> {
+    pub fn get(&self) -> Option<&[T]> {
+        self.graph.nodes[self.node.0].input.as_deref()
+    }
+
+    pub fn set(&mut self, input: Vec<T>) {
+        self.graph.nodes[self.node.0].input = Some(input);
+        self.graph.input_changed(self.node, None);
+    }
+
+    pub fn insert(&mut self, index: usize, value: T) -> Option<()> {
+        match self.graph.nodes[self.node.0].input {
+            None => None,
+            Some(ref mut input) => {
+                input.insert(index, value);
+                self.graph.input_changed(self.node, None);
+                Some(())
+            }
+        }
+    }
+
+    /// Replaces the value at `index`, which incremental nodes can take into account without
+    /// recomputing everything, see [`Graph::set_incremental`].
+    pub fn update(&mut self, index: usize, value: T) -> Option<()> {
+        let input = self.graph.nodes[self.node.0].input.as_mut()?;
+        let old = mem::replace(input.get_mut(index)?, value.clone());
+        let change = Change {
+            index,
+            old,
+            new: value,
+        };
+        self.graph.input_changed(self.node, Some(change));
+        Some(())
+    }
+}
+
+// The node of a builder was just added, so setting its properties cannot fail.
+const BUILT: &str = "the node being built exists";
+
+/// Configures a node freshly added to a [`Graph`].
+pub struct NodeBuilder<'a, T = f32> {
+    graph: &'a mut Graph<T>,
+    id: NodeId,
+}
+
+impl<T: Value> NodeBuilder<'_, T> {
+    pub fn input(self, input: Vec<T>) -> Self {
+        self.graph.input(self.id).expect(BUILT).set(input);
+        self
+    }
+
+    pub fn name(self, name: impl Into<String>) -> Result<Self, GraphError> {
+        self.graph.set_name(self.id, name)?;
+        Ok(self)
+    }
+
+    pub fn description(self, description: impl Into<String>) -> Self {
+        self.graph
+            .set_description(self.id, description)
+            .expect(BUILT);
+        self
+    }
+
+    pub fn format(self, format: Format) -> Self {
+        self.graph.set_format(self.id, format).expect(BUILT);
+        self
+    }
+
+    pub fn port(self, port: &str, child: NodeId) -> Result<Self, GraphError> {
+        self.graph.connect_port(self.id, port, child)?;
+        Ok(self)
+    }
+
+    pub fn child(self, child: NodeId) -> Result<Self, GraphError> {
+        self.graph.add_children(self.id, child)?;
+        Ok(self)
+    }
+
+    /// Feeds the output of the node being built into `dst`, see [`Graph::connect`].
+    pub fn feeds(self, dst: NodeId) -> Result<Self, GraphError> {
+        self.graph.connect(self.id, dst)?;
+        Ok(self)
+    }
+
+    pub fn arity(self, arity: usize) -> Self {
+        self.graph.set_arity(self.id, arity).expect(BUILT);
+        self
+    }
+
+    pub fn in_place(self) -> Self {
+        self.graph.set_in_place(self.id, true).expect(BUILT);
+        self
+    }
+
+    pub fn sensitive(self) -> Self {
+        self.graph.set_sensitive(self.id, true).expect(BUILT);
+        self
+    }
+
+    pub fn impure(self) -> Self {
+        self.graph.set_pure(self.id, false).expect(BUILT);
+        self
+    }
+
+    pub fn sampling(self, mode: SamplingMode) -> Self {
+        self.graph.set_sampling(self.id, mode).expect(BUILT);
+        self
+    }
+
+    pub fn require_capability(self, capability: impl Into<String>) -> Self {
+        self.graph
+            .require_capability(self.id, capability)
+            .expect(BUILT);
+        self
+    }
+
+    pub fn build(self) -> NodeId {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interner::HashInterner;
+    use crate::rng::Sampler;
+
+    fn round(x: f32, precision: u32) -> f32 {
+        let m = 10i32.pow(precision) as f32;
+        (x * m).round() / m
+    }
+
+    #[test]
+    fn test_1() {
+        let mut graph = Graph::new();
+
+        let node_1 = graph.add_node(|input| vec![input.first().unwrap().powf(3.0)]);
+        let node_2 = graph.add_node(|input| vec![input.first().unwrap() + input.get(1).unwrap()]);
+        let node_3 = graph.add_node(|input| vec![input.first().unwrap().sin()]);
+        let node_4 = graph.add_node(|input| vec![input.first().unwrap() * input.get(1).unwrap()]);
+        let node_5 = graph.add_node(|input| vec![input.first().unwrap() + input.get(1).unwrap()]);
+
+        graph.input(node_1).unwrap().set(vec![3.0]);
+        graph.input(node_2).unwrap().set(vec![2.0]);
+        graph.input(node_4).unwrap().set(vec![2.0]);
+        graph.input(node_5).unwrap().set(vec![1.0]);
+
+        graph.add_children(node_2, node_1).unwrap();
+        graph.add_children(node_3, node_2).unwrap();
+        graph.add_children(node_4, node_3).unwrap();
+        graph.add_children(node_5, node_4).unwrap();
+
+        let output = graph.compute(node_5).unwrap();
+
+        assert_eq!(round(output[0], 5), -0.32727);
+    }
+
+    fn build_graph(graph: &mut Graph) -> [NodeId; 4] {
+        let node_1 = graph.add_node(|input| vec![input.first().unwrap().powf(3.0)]);
+        let node_2 = graph.add_node(|input| vec![input.first().unwrap() + input.get(1).unwrap()]);
+        let node_3 = graph.add_node(|input| vec![input.first().unwrap().sin()]);
+        let node_4 = graph.add_node(|input| vec![input.first().unwrap() * input.get(1).unwrap()]);
+        let node_5 = graph.add_node(|input| vec![input.first().unwrap() + input.get(1).unwrap()]);
+
+        let node_input_1 = graph.add_node(|input| input);
+        let node_input_2 = graph.add_node(|input| input);
+        let node_input_3 = graph.add_node(|input| input);
+
+        graph.add_children(node_1, node_input_3).unwrap();
+
+        graph.add_children(node_2, node_1).unwrap();
+        graph.add_children(node_2, node_input_2).unwrap();
+
+        graph.add_children(node_3, node_2).unwrap();
+
+        graph.add_children(node_4, node_3).unwrap();
+        graph.add_children(node_4, node_input_2).unwrap();
+
+        graph.add_children(node_5, node_4).unwrap();
+        graph.add_children(node_5, node_input_1).unwrap();
+
+        [node_input_1, node_input_2, node_input_3, node_5]
+    }
+
+    #[test]
+    fn test_2() {
+        let mut graph = Graph::new();
+        let [input_1, input_2, input_3, node_5] = build_graph(&mut graph);
+
+        graph.input(input_1).unwrap().set(vec![1.0]);
+        graph.input(input_2).unwrap().set(vec![2.0]);
+        graph.input(input_3).unwrap().set(vec![3.0]);
+
+        let output = graph.compute(node_5).unwrap();
+
+        assert_eq!(round(output[0], 5), -0.32727);
+    }
+
+    #[test]
+    fn test_3() {
+        let mut graph = Graph::new();
+        let [input_1, input_2, input_3, node_5] = build_graph(&mut graph);
+
+        graph.input(input_1).unwrap().set(vec![2.0]);
+        graph.input(input_2).unwrap().set(vec![3.0]);
+        graph.input(input_3).unwrap().set(vec![4.0]);
+
+        let output = graph.compute(node_5).unwrap();
+
+        assert_eq!(round(output[0], 5), -0.56656);
+    }
+
+    #[test]
+    fn test_4() {
+        let mut graph = Graph::new();
+
+        let node_1 = graph.add_node(|input| vec![input.first().unwrap().powf(3.0)]);
+        let node_2 = graph.add_node(|input| vec![input.first().unwrap() + input.get(1).unwrap()]);
+
+        graph.add_children(node_1, node_2).unwrap();
+
+        match graph.add_children(node_2, node_1) {
+            Err(GraphError::CycleDetected(path)) => assert_eq!(path, [node_2, node_1, node_2]),
+            result => panic!("expected a cycle, got {:?}", result),
+        }
+        assert!(graph.inner(node_2).down.is_empty());
+
+        graph.input(node_2).unwrap().set(vec![1.0, 2.0]);
+        assert_eq!(graph.compute(node_2).unwrap(), [3.0]);
+    }
+
+    #[test]
+    fn test_cycle_through_diamond() {
+        let mut graph = Graph::new();
+
+        let node_1 = graph.add_node(|input| input);
+        let node_2 = graph.add_node(|input| input);
+        let node_3 = graph.add_node(|input| input);
+        let node_4 = graph.add_node(|input| input);
+
+        graph.add_children(node_2, node_1).unwrap();
+        graph.add_children(node_3, node_1).unwrap();
+        graph.add_children(node_4, node_2).unwrap();
+        graph.add_children(node_4, node_3).unwrap();
+
+        let error = graph.add_children(node_1, node_4).unwrap_err();
+        assert_eq!(error.to_string(), "cycle detected: #0 -> #3 -> #1 -> #0");
+
+        let error = graph.add_children(node_1, node_1).unwrap_err();
+        assert_eq!(error.to_string(), "cycle detected: #0 -> #0");
+    }
+
+    #[test]
+    fn test_cache_invalidation() {
+        let mut graph = Graph::new();
+        let [input_1, input_2, input_3, node_5] = build_graph(&mut graph);
+
+        graph.input(input_1).unwrap().set(vec![2.0]);
+        graph.input(input_2).unwrap().set(vec![3.0]);
+        graph.input(input_3).unwrap().set(vec![4.0]);
+
+        let output = graph.compute(node_5).unwrap();
+        assert_eq!(round(output[0], 5), -0.56656);
+
+        graph.input(input_1).unwrap().set(vec![3.0]);
+
+        let output = graph.compute(node_5).unwrap();
+        assert_eq!(round(output[0], 5), 0.43344);
+    }
+
+    #[test]
+    fn test_take_output() {
+        let mut graph = Graph::new();
+
+        let node_1 = graph.add_node(|input| input.iter().map(|x| x * 2.0).collect());
+        let node_2 = graph.add_node(|input| vec![input.iter().sum()]);
+
+        graph.input(node_1).unwrap().set(vec![1.0, 2.0, 3.0]);
+
+        graph.add_children(node_2, node_1).unwrap();
+
+        assert_eq!(graph.take_output(node_1).unwrap(), vec![2.0, 4.0, 6.0]);
+        assert!(graph.inner(node_1).cache.is_none());
+
+        assert_eq!(graph.take_output(node_2).unwrap(), vec![12.0]);
+        assert_eq!(graph.compute(node_2).unwrap()[0], 12.0);
+    }
+
+    #[test]
+    fn test_in_place() {
+        let mut graph = Graph::new();
+
+        let node_1 = graph.add_node(|input| input);
+        let node_2 = graph.add_node(|mut input| {
+            input.iter_mut().for_each(|x| *x *= 2.0);
+            input
+        });
+        let node_3 = graph.add_node(|mut input| {
+            input.iter_mut().for_each(|x| *x += 1.0);
+            input
+        });
+        let node_4 = graph.add_node(|input| vec![input.iter().sum()]);
+
+        graph.set_in_place(node_2, true).unwrap();
+        graph.set_in_place(node_3, true).unwrap();
+
+        graph.input(node_1).unwrap().set(vec![1.0, 2.0]);
+
+        graph.add_children(node_2, node_1).unwrap();
+        graph.add_children(node_3, node_2).unwrap();
+        graph.add_children(node_4, node_2).unwrap();
+
+        assert_eq!(graph.compute(node_3).unwrap(), [3.0, 5.0]);
+
+        // node_2 has two consumers so its buffer was copied, node_1 has one so it was moved.
+        assert!(graph.inner(node_1).cache.is_none());
+        assert!(graph.inner(node_2).cache.is_some());
+
+        assert_eq!(graph.compute(node_4).unwrap(), [6.0]);
+    }
+
+    #[test]
+    fn test_in_place_readers() {
+        let mut graph = Graph::new();
+        let x = graph.node(|input| input).input(vec![1.0]).build();
+        let doubled = graph
+            .node(|mut input| {
+                input[0] *= 2.0;
+                input
+            })
+            .child(x)
+            .unwrap()
+            .in_place()
+            .build();
+        let reader = graph
+            .contextual_node(move |input, context| {
+                let x = context.output(x).map_or(-1.0, |x| x[0]);
+                Ok(vec![input[0], x])
+            })
+            .child(doubled)
+            .unwrap()
+            .build();
+
+        // The buffer of x was moved into `doubled` during the same evaluation.
+        assert_eq!(graph.compute(reader).unwrap(), [2.0, -1.0]);
+        assert!(graph.inner(x).cache.is_none());
+
+        graph.set_history(x, 2).unwrap();
+        graph.input(x).unwrap().set(vec![3.0]);
+        assert_eq!(graph.compute(reader).unwrap(), [6.0, 3.0]);
+        assert!(graph.inner(x).cache.is_some());
+    }
+
+    #[test]
+    fn test_compute_many_in_place() {
+        let mut graph = Graph::new();
+        let child = graph.node(|input| input).input(vec![1.0, 2.0]).build();
+        let parent = graph
+            .node(|mut input| {
+                input.iter_mut().for_each(|x| *x *= 10.0);
+                input
+            })
+            .child(child)
+            .unwrap()
+            .in_place()
+            .build();
+        let total = graph
+            .node(|input| vec![input.iter().sum()])
+            .child(parent)
+            .unwrap()
+            .in_place()
+            .build();
+
+        assert_eq!(
+            graph.compute_many(&[child, parent]).unwrap(),
+            [vec![1.0, 2.0], vec![10.0, 20.0]]
+        );
+        graph.input(child).unwrap().set(vec![3.0]);
+        assert_eq!(
+            graph.compute_many(&[total, child]).unwrap(),
+            [vec![30.0], vec![3.0]]
+        );
+        // Nodes that were not requested are still moved.
+        assert!(graph.inner(parent).cache.is_none());
+
+        // A candidate may not move the output of a node requested alongside the one it shadows.
+        let source = graph.node(|input| input).input(vec![4.0]).build();
+        let current = graph.node(|input| input).input(vec![5.0]).build();
+        let candidate = graph
+            .node(|mut input| {
+                input[0] += 1.0;
+                input
+            })
+            .child(source)
+            .unwrap()
+            .in_place()
+            .build();
+        graph
+            .set_shadow(current, candidate, crate::shadow::max_abs_diff)
+            .unwrap();
+        assert_eq!(
+            graph.compute_many(&[source, current]).unwrap(),
+            [vec![4.0], vec![5.0]]
+        );
+        assert_eq!(graph.shadow_report(current).unwrap().failures, 0);
+        assert_eq!(graph.inner(source).output(), Some(&[4.0][..]));
+    }
+
+    #[test]
+    fn test_sensitive() {
+        let mut graph = Graph::new();
+        let node = graph.add_node(|input| input);
+
+        assert_eq!(graph.inner(node).display_value(&[1.0]), "[1.0]");
+
+        graph.set_sensitive(node, true).unwrap();
+        assert_eq!(graph.inner(node).display_value(&[1.0]), "<redacted>");
+    }
+
+    #[test]
+    fn test_impure() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        let mut graph = Graph::new();
+
+        let node_1 = graph.add_node(|_| vec![CALLS.fetch_add(1, Ordering::SeqCst) as f32]);
+        let node_2 = graph.add_node(|input| vec![input.first().unwrap() * 10.0]);
+
+        graph.add_children(node_2, node_1).unwrap();
+        graph.set_pure(node_1, false).unwrap();
+
+        assert_eq!(graph.compute(node_2).unwrap(), [0.0]);
+        assert_eq!(graph.compute(node_2).unwrap(), [10.0]);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+
+        // Once pure again the last results are served from cache.
+        graph.set_pure(node_1, true).unwrap();
+        assert_eq!(graph.compute(node_2).unwrap(), [10.0]);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+        assert!(!graph.inner(node_2).volatile);
+    }
+
+    #[test]
+    fn test_builder() {
+        let mut graph = Graph::new();
+
+        let node_1 = graph
+            .node(|input| vec![input.first().unwrap().powf(3.0)])
+            .input(vec![3.0])
+            .build();
+        let node_2 = graph
+            .node(|input| vec![input.first().unwrap() + input.get(1).unwrap()])
+            .child(node_1)
+            .unwrap()
+            .input(vec![2.0])
+            .build();
+        let node_3 = graph
+            .node(|input| input.iter().map(|x| x * 2.0).collect())
+            .child(node_2)
+            .unwrap()
+            .in_place()
+            .build();
+
+        assert_eq!(graph.len(), 3);
+        assert_eq!(graph.compute(node_3).unwrap(), [58.0]);
+
+        graph.input(node_1).unwrap().set(vec![1.0]);
+        assert_eq!(graph.compute(node_3).unwrap(), [6.0]);
+    }
+
+    #[test]
+    fn test_clone() {
+        let mut graph = Graph::new();
+        let [input_1, input_2, input_3, node_5] = build_graph(&mut graph);
+
+        graph.input(input_1).unwrap().set(vec![2.0]);
+        graph.input(input_2).unwrap().set(vec![3.0]);
+        graph.input(input_3).unwrap().set(vec![4.0]);
+        graph.compute(node_5).unwrap();
+
+        let mut copy = graph.clone();
+        copy.input(input_1).unwrap().set(vec![3.0]);
+
+        assert_eq!(round(copy.compute(node_5).unwrap()[0], 5), 0.43344);
+        assert_eq!(round(graph.compute(node_5).unwrap()[0], 5), -0.56656);
+        assert_eq!(graph.nodes().count(), 8);
+    }
+
+    #[test]
+    fn test_closures() {
+        let mut graph = Graph::new();
+
+        let exponent = 3.0;
+        let table = [10.0, 20.0, 30.0];
+
+        let node_1 = graph.add_node(move |input| input.iter().map(|x| x.powf(exponent)).collect());
+        let node_2 = graph.add_node(move |input| {
+            input
+                .iter()
+                .map(|x| table.get(*x as usize).copied().unwrap_or(0.0))
+                .collect()
+        });
+
+        graph.input(node_1).unwrap().set(vec![1.0, 2.0]);
+        graph.add_children(node_2, node_1).unwrap();
+
+        assert_eq!(graph.compute(node_2).unwrap(), [20.0, 0.0]);
+
+        let mut copy = graph.clone();
+        copy.input(node_1).unwrap().set(vec![0.0]);
+        assert_eq!(copy.compute(node_2).unwrap(), [10.0]);
+    }
+
+    #[test]
+    fn test_fallible() {
+        let mut graph = Graph::new();
+
+        let node_1 = graph.add_node(|input| input);
+        let node_2 = graph.add_fallible_node(|input| {
+            let x = *input.first().ok_or(NodeError::MissingInput(0))?;
+            if x < 0.0 {
+                return Err(NodeError::failed("negative input"));
+            }
+            Ok(vec![x.sqrt()])
+        });
+        let node_3 = graph.add_node(|input| vec![input.iter().sum()]);
+
+        graph.add_children(node_2, node_1).unwrap();
+        graph.add_children(node_3, node_2).unwrap();
+
+        assert_eq!(
+            graph.compute(node_3),
+            Err(GraphError::MissingInput {
+                node: node_2,
+                index: 0
+            })
+        );
+
+        graph.input(node_1).unwrap().set(vec![-4.0]);
+        let error = graph.compute(node_3).unwrap_err();
+        assert_eq!(error.to_string(), "node #1 failed: negative input");
+        assert!(graph.inner(node_3).cache.is_none());
+
+        graph.input(node_1).unwrap().set(vec![4.0]);
+        assert_eq!(graph.compute(node_3).unwrap(), [2.0]);
+    }
+
+    #[test]
+    fn test_interner() {
+        let mut graph = Graph::new();
+        graph.set_interner(HashInterner::new());
+
+        let node_1 = graph.add_node(|input| input);
+        let node_2 = graph.add_node(|input| input.iter().map(|x| x.min(1.0)).collect());
+        let node_3 = graph.add_node(|input| input.iter().map(|x| x.min(1.0)).collect());
+
+        graph.input(node_1).unwrap().set(vec![1.0, 0.0]);
+        graph.input(node_2).unwrap().set(vec![5.0, 0.0]);
+        graph.input(node_3).unwrap().set(vec![2.0, 0.0]);
+
+        graph.compute(node_1).unwrap();
+        graph.compute(node_2).unwrap();
+        graph.compute(node_3).unwrap();
+
+        let shared = |a: NodeId, b: NodeId| {
+            Arc::ptr_eq(
+                graph.inner(a).cache.as_ref().unwrap(),
+                graph.inner(b).cache.as_ref().unwrap(),
+            )
+        };
+        assert!(shared(node_1, node_2));
+        assert!(shared(node_2, node_3));
+
+        // Taking a shared output copies it and leaves the other nodes untouched.
+        assert_eq!(graph.take_output(node_2).unwrap(), [1.0, 0.0]);
+        assert_eq!(graph.compute(node_3).unwrap(), [1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_value_types() {
+        let mut graph: Graph<f64> = Graph::default();
+        let node_1 = graph.add_node(|input| input);
+        let node_2 = graph.add_node(|input| vec![input.iter().map(|x| x.exp()).sum()]);
+
+        graph.input(node_1).unwrap().set(vec![0.0, 1.0]);
+        graph.add_children(node_2, node_1).unwrap();
+        assert_eq!(graph.compute(node_2).unwrap(), [1.0 + 1f64.exp()]);
+
+        let mut graph: Graph<i64> = Graph::default();
+        let node_1 = graph.add_node(|input| vec![input.iter().product()]);
+        graph.input(node_1).unwrap().set(vec![i64::MAX / 2, 2]);
+        assert_eq!(graph.compute(node_1).unwrap(), [i64::MAX - 1]);
+
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct Complex(f32, f32);
+
+        let mut graph: Graph<Complex> = Graph::default();
+        let node_1 = graph.add_node(|input| {
+            let product = input.iter().fold(Complex(1.0, 0.0), |a, b| {
+                Complex(a.0 * b.0 - a.1 * b.1, a.0 * b.1 + a.1 * b.0)
+            });
+            vec![product]
+        });
+        graph
+            .input(node_1)
+            .unwrap()
+            .set(vec![Complex(0.0, 1.0), Complex(0.0, 1.0)]);
+        assert_eq!(graph.compute(node_1).unwrap(), [Complex(-1.0, 0.0)]);
+    }
+
+    #[test]
+    fn test_diamond() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut graph = Graph::new();
+
+        let counter = calls.clone();
+        let source = graph.add_node(move |input| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            input
+        });
+        let left = graph.add_node(|input| input.iter().map(|x| x + 1.0).collect());
+        let right = graph.add_node(|input| input.iter().map(|x| x * 2.0).collect());
+        let sink = graph.add_node(|input| vec![input.iter().sum()]);
+
+        graph.add_children(left, source).unwrap();
+        graph.add_children(right, source).unwrap();
+        graph.add_children(sink, left).unwrap();
+        graph.add_children(sink, right).unwrap();
+        graph.input(source).unwrap().set(vec![1.0]);
+
+        assert_eq!(graph.compute(sink).unwrap(), [4.0]);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        // Impure nodes run once per evaluation, not once per path reaching them.
+        graph.set_pure(source, false).unwrap();
+        assert_eq!(graph.compute(sink).unwrap(), [4.0]);
+        assert_eq!(graph.compute(sink).unwrap(), [4.0]);
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let mut graph = Graph::new();
+        let exposure = graph.node(|input| input).input(vec![2.0]).build();
+        let risk = graph
+            .node(|input| vec![input[0] * 10.0])
+            .child(exposure)
+            .unwrap()
+            .require_capability("risk")
+            .build();
+        let report = graph
+            .node(|input| vec![input[0] + 1.0])
+            .child(risk)
+            .unwrap()
+            .build();
+
+        let refused = GraphError::Unauthorized {
+            node: risk,
+            capability: "risk".to_owned(),
+        };
+        assert_eq!(graph.compute(risk), Err(refused.clone()));
+        assert_eq!(graph.compute_with(risk, &["audit"]), Err(refused.clone()));
+        assert_eq!(graph.take_output(risk), Err(refused.clone()));
+        assert_eq!(
+            graph.compute_with(risk, &["audit", "risk"]).unwrap(),
+            [20.0]
+        );
+
+        // Restrictions apply to the requested sink only.
+        assert_eq!(graph.compute(report).unwrap(), [21.0]);
+
+        graph.set_history(risk, 2).unwrap();
+        graph.input(exposure).unwrap().set(vec![3.0]);
+        assert_eq!(graph.compute(report).unwrap(), [31.0]);
+        assert_eq!(graph.history(risk, &[]).err(), Some(refused.clone()));
+        assert_eq!(graph.history(risk, &["risk"]).unwrap().count(), 1);
+        assert_eq!(graph.take_output_with(risk, &["risk"]).unwrap(), [30.0]);
+
+        let reader = graph
+            .contextual_node(move |_, context| Ok(context.output(risk).unwrap_or(&[]).to_vec()))
+            .build();
+        graph.compute_with(risk, &["risk"]).unwrap();
+        assert!(graph.compute(reader).unwrap().is_empty());
+
+        let attempts: Vec<_> = (0..5).map(|_| (risk, "risk".to_owned())).collect();
+        assert_eq!(graph.refused_attempts(), attempts);
+        for _ in 0..REFUSED_CAPACITY {
+            assert_eq!(graph.compute(risk), Err(refused.clone()));
+        }
+        assert_eq!(graph.refused_attempts().len(), REFUSED_CAPACITY);
+    }
+
+    #[test]
+    fn test_dirty_propagation() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut graph = Graph::new();
+        let counted = |graph: &mut Graph| {
+            let counter = calls.clone();
+            graph.add_node(move |input: Vec<f32>| {
+                counter.fetch_add(1, Ordering::Relaxed);
+                vec![input.iter().sum()]
+            })
+        };
+
+        // `source` fans out to two nodes which both feed `left` and `right`, `other` is unrelated.
+        let source = counted(&mut graph);
+        let a = counted(&mut graph);
+        let b = counted(&mut graph);
+        let left = counted(&mut graph);
+        let right = counted(&mut graph);
+        let other = counted(&mut graph);
+        for (parent, child) in [
+            (a, source),
+            (b, source),
+            (left, a),
+            (left, b),
+            (right, a),
+            (right, b),
+        ] {
+            graph.add_children(parent, child).unwrap();
+        }
+        graph.input(source).unwrap().set(vec![1.0]);
+        graph.input(other).unwrap().set(vec![5.0]);
+
+        assert_eq!(graph.compute(left).unwrap(), [2.0]);
+        assert_eq!(graph.compute(right).unwrap(), [2.0]);
+        assert_eq!(graph.compute(other).unwrap(), [5.0]);
+        assert_eq!(calls.load(Ordering::Relaxed), 6);
+        assert!(graph.nodes().all(|node| !graph.is_dirty(node).unwrap()));
+
+        graph.input(source).unwrap().set(vec![2.0]);
+        graph.input(source).unwrap().set(vec![3.0]);
+        graph.input(a).unwrap().set(vec![1.0]);
+        assert!([source, a, b, left, right]
+            .iter()
+            .all(|node| graph.is_dirty(*node).unwrap()));
+        assert!(!graph.is_dirty(other).unwrap());
+
+        assert_eq!(graph.compute(left).unwrap(), [7.0]);
+        assert_eq!(graph.compute(right).unwrap(), [7.0]);
+        assert_eq!(graph.compute(other).unwrap(), [5.0]);
+        assert_eq!(calls.load(Ordering::Relaxed), 11);
+    }
+
+    #[test]
+    fn test_unknown_node() {
+        let mut other = Graph::new();
+        other.add_node(|input| input);
+        let foreign = other.add_node(|input| input);
+
+        let mut graph = Graph::new();
+        let node = graph.add_node(|input| input);
+        let unknown = GraphError::UnknownNode(foreign);
+
+        assert_eq!(graph.compute(foreign), Err(unknown.clone()));
+        assert_eq!(graph.compute_with(foreign, &["risk"]), Err(unknown.clone()));
+        assert_eq!(graph.view(foreign).err(), Some(unknown.clone()));
+        assert_eq!(graph.take_output(foreign), Err(unknown.clone()));
+        assert_eq!(graph.add_children(node, foreign), Err(unknown.clone()));
+        assert_eq!(graph.add_children(foreign, node), Err(unknown.clone()));
+        assert_eq!(graph.set_pure(foreign, false), Err(unknown.clone()));
+        assert_eq!(
+            graph.require_capability(foreign, "risk"),
+            Err(unknown.clone())
+        );
+        assert_eq!(graph.is_dirty(foreign), Err(unknown.clone()));
+        assert_eq!(graph.input(foreign).err(), Some(unknown));
+        assert!(graph.compute(node).unwrap().is_empty());
+
+        let removed = graph.node(|input| input).input(vec![1.0]).build();
+        graph.remove_node(removed).unwrap();
+        assert_eq!(
+            graph.input(removed).err(),
+            Some(GraphError::UnknownNode(removed))
+        );
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        let mut graph = Graph::new();
+        let mut node = graph.node(|input| input).input(vec![0.0]).build();
+        for _ in 0..100_000 {
+            node = graph
+                .node(|input| vec![input[0] + 1.0])
+                .child(node)
+                .unwrap()
+                .build();
+        }
+        assert_eq!(graph.compute(node).unwrap(), [100_000.0]);
+
+        graph.input(NodeId(0)).unwrap().set(vec![1.0]);
+        assert!(graph.is_dirty(node).unwrap());
+        assert_eq!(graph.compute(node).unwrap(), [100_001.0]);
+
+        graph.set_pure(NodeId(0), false).unwrap();
+        assert_eq!(graph.compute(node).unwrap(), [100_001.0]);
+    }
+
+    #[test]
+    fn test_context() {
+        let mut graph = Graph::new();
+        let keys = graph.node(|input| input).input(vec![1.0, 2.0]).build();
+        let values = graph.node(|input| input).input(vec![10.0, 20.0]).build();
+        let join = graph
+            .contextual_node(move |input, context| {
+                let values = context
+                    .output(values)
+                    .ok_or_else(|| NodeError::failed("values not computed"))?;
+                Ok(input.iter().zip(values).map(|(k, v)| k * v).collect())
+            })
+            .child(keys)
+            .unwrap()
+            .build();
+
+        let error = graph.compute(join).unwrap_err();
+        assert_eq!(error.to_string(), "node #2 failed: values not computed");
+
+        graph.compute(values).unwrap();
+        assert_eq!(graph.compute(join).unwrap(), [10.0, 40.0]);
+
+        graph.input(values).unwrap().set(vec![1.0, 1.0]);
+        graph.input(keys).unwrap().set(vec![3.0, 4.0]);
+        assert!(graph.compute(join).is_err());
+    }
+
+    #[test]
+    fn test_registered_node() {
+        let mut registry = OpRegistry::new();
+        registry.register("double", |input: Vec<f32>| {
+            Ok(input.iter().map(|x| x * 2.0).collect())
+        });
+        assert!(registry.contains("double"));
+
+        let mut graph = Graph::new();
+        let node = graph
+            .registered_node(&registry, "double")
+            .unwrap()
+            .input(vec![1.5])
+            .build();
+        assert_eq!(graph.op(node), Some("double"));
+        assert_eq!(graph.compute(node).unwrap(), [3.0]);
+
+        assert_eq!(
+            graph.registered_node(&registry, "triple").err(),
+            Some(GraphError::UnknownOp("triple".to_owned()))
+        );
+        assert_eq!(graph.len(), 1);
+    }
+
+    #[test]
+    fn test_placeholder_node() {
+        let mut graph = Graph::new();
+        let x = graph.node(|input| input).input(vec![2.0]).build();
+        let y = graph
+            .placeholder_node("plugin.double")
+            .child(x)
+            .unwrap()
+            .build();
+        let z = graph
+            .placeholder_node("plugin.negate")
+            .child(y)
+            .unwrap()
+            .build();
+
+        let unresolved = |node, op: &str| GraphError::UnresolvedOp {
+            node,
+            op: op.to_owned(),
+        };
+        assert_eq!(graph.compute(z), Err(unresolved(y, "plugin.double")));
+
+        let mut registry = OpRegistry::new();
+        registry.register("plugin.double", |input: Vec<f32>| Ok(vec![input[0] * 2.0]));
+        assert_eq!(
+            graph.resolve_ops(&registry),
+            Err(unresolved(z, "plugin.negate"))
+        );
+        assert_eq!(graph.compute(y), Err(unresolved(y, "plugin.double")));
+
+        registry.register("plugin.negate", |input: Vec<f32>| Ok(vec![-input[0]]));
+        registry.describe("plugin.negate", "negated input");
+        graph.resolve_ops(&registry).unwrap();
+        assert_eq!(graph.compute(z).unwrap(), [-4.0]);
+        assert_eq!(graph.op(z), Some("plugin.negate"));
+        assert_eq!(graph.description(z), Some("negated input"));
+        assert_eq!(graph.description(y), None);
+    }
+
+    #[test]
+    fn test_names() {
+        let mut graph = Graph::new();
+        let price = graph.add_node_named("price", |input| input).unwrap();
+        let total = graph
+            .node(|input| vec![input.iter().sum()])
+            .name("total")
+            .unwrap()
+            .child(price)
+            .unwrap()
+            .build();
+
+        assert_eq!(graph.node_by_name("price"), Some(price));
+        assert_eq!(graph.name(total), Some("total"));
+        assert_eq!(graph.node_by_name("missing"), None);
+
+        assert_eq!(
+            graph.add_node_named("price", |input| input),
+            Err(GraphError::DuplicateName("price".to_owned()))
+        );
+        graph.set_name(total, "sum").unwrap();
+        assert_eq!(graph.node_by_name("total"), None);
+        assert_eq!(graph.node_by_name("sum"), Some(total));
+        graph.set_name(total, "sum").unwrap();
+    }
+
+    #[test]
+    fn test_compute_all_named() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let mut graph = Graph::new();
+        let source = graph
+            .node(move |input| {
+                counter.fetch_add(1, Ordering::Relaxed);
+                input
+            })
+            .name("source")
+            .unwrap()
+            .input(vec![2.0])
+            .impure()
+            .build();
+        graph
+            .node(|input| vec![input[0] * 10.0])
+            .name("revenue")
+            .unwrap()
+            .child(source)
+            .unwrap()
+            .build();
+        graph
+            .node(|input| vec![input[0] + 1.0])
+            .name("cost")
+            .unwrap()
+            .child(source)
+            .unwrap()
+            .build();
+        graph.node(|input| input).child(source).unwrap().build();
+
+        let figures = graph.compute_all_named().unwrap();
+        assert_eq!(figures.len(), 2);
+        assert_eq!(figures["revenue"], [20.0]);
+        assert_eq!(figures["cost"], [3.0]);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_compute_many() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        // Impure source returning a new value on every evaluation.
+        let ticks = Arc::new(AtomicU32::new(0));
+        let mut graph = Graph::new();
+        let source = graph
+            .node(move |_| vec![ticks.fetch_add(1, Ordering::Relaxed) as f32])
+            .impure()
+            .build();
+        let double = graph
+            .node(|input| vec![input[0] * 2.0])
+            .child(source)
+            .unwrap()
+            .build();
+        let offset = graph
+            .node(|input| vec![input[0] + 10.0])
+            .child(source)
+            .unwrap()
+            .build();
+
+        assert_eq!(
+            graph.compute_many(&[double, offset, double]).unwrap(),
+            [[0.0], [10.0], [0.0]]
+        );
+        assert_eq!(
+            graph.compute_many(&[offset, double]).unwrap(),
+            [[11.0], [2.0]]
+        );
+        assert!(graph.compute_many(&[]).unwrap().is_empty());
+        assert_eq!(
+            graph.compute_many(&[double, NodeId(7)]),
+            Err(GraphError::UnknownNode(NodeId(7)))
+        );
+    }
+
+    #[test]
+    fn test_history() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::time::Duration;
+
+        let now = Arc::new(AtomicU64::new(0));
+        let mut graph = Graph::new();
+        let clock = now.clone();
+        graph.set_clock(move || {
+            SystemTime::UNIX_EPOCH + Duration::from_secs(clock.load(Ordering::Relaxed))
+        });
+
+        let node = graph.node(|input| input).input(vec![1.0]).build();
+        graph.set_history(node, 2).unwrap();
+        for (time, value) in [(10, 1.0), (20, 2.0), (30, 3.0)] {
+            now.store(time, Ordering::Relaxed);
+            graph.input(node).unwrap().set(vec![value]);
+            graph.compute(node).unwrap();
+            graph.compute(node).unwrap();
+        }
+
+        let history: Vec<_> = graph.history(node, &[]).unwrap().collect();
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        assert_eq!(history, [(at(20), &[2.0][..]), (at(30), &[3.0][..])]);
+
+        graph.set_history(node, 0).unwrap();
+        assert_eq!(graph.history(node, &[]).unwrap().count(), 0);
+        assert_eq!(
+            graph.set_history(NodeId(9), 2),
+            Err(GraphError::UnknownNode(NodeId(9)))
+        );
+        assert!(graph.history(NodeId(9), &[]).is_err());
+    }
+
+    #[test]
+    fn test_compute_as_of() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::time::Duration;
+
+        struct Counting(Arc<AtomicU64>);
+
+        impl Interner<f32> for Counting {
+            fn intern(&mut self, value: Vec<f32>) -> Arc<Vec<f32>> {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                Arc::new(value)
+            }
+        }
+
+        let now = Arc::new(AtomicU64::new(0));
+        let clock = now.clone();
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let mut graph = Graph::new();
+        graph.set_clock(move || at(clock.load(Ordering::Relaxed)));
+        let interned = Arc::new(AtomicU64::new(0));
+        graph.set_interner(Counting(interned.clone()));
+
+        let price = graph.node(|input| input).input(vec![1.0]).build();
+        let quantity = graph.node(|input| input).build();
+        let total = graph
+            .node(|input| vec![input[0] * input[1]])
+            .child(price)
+            .unwrap()
+            .child(quantity)
+            .unwrap()
+            .build();
+        now.store(1, Ordering::Relaxed);
+        graph.set_history(price, 4).unwrap();
+        graph.set_history(quantity, 4).unwrap();
+
+        now.store(10, Ordering::Relaxed);
+        graph.input(quantity).unwrap().set(vec![2.0]);
+        now.store(20, Ordering::Relaxed);
+        graph.input(price).unwrap().set(vec![5.0]);
+        now.store(30, Ordering::Relaxed);
+        graph.input(quantity).unwrap().insert(0, 3.0).unwrap();
+        graph.input(quantity).unwrap().set(vec![3.0]);
+
+        assert_eq!(graph.compute_as_of(total, at(15)).unwrap(), [2.0]);
+        assert_eq!(graph.compute_as_of(total, at(25)).unwrap(), [10.0]);
+        assert_eq!(interned.load(Ordering::Relaxed), 0);
+        assert_eq!(graph.compute(total).unwrap(), [15.0]);
+        assert_eq!(interned.load(Ordering::Relaxed), 3);
+        assert_eq!(
+            graph.compute_as_of(total, at(0)),
+            Err(GraphError::NoHistory(quantity))
+        );
+
+        graph.set_history(price, 0).unwrap();
+        assert_eq!(
+            graph.compute_as_of(total, at(25)),
+            Err(GraphError::NoHistory(price))
+        );
+    }
+
+    #[test]
+    fn test_threads() {
+        use std::thread;
+
+        fn assert_send_sync<S: Send + Sync>() {}
+        assert_send_sync::<Graph>();
+
+        let mut graph = Graph::new();
+        graph.set_interner(HashInterner::new());
+        let x = graph.node(|input| input).input(vec![2.0]).build();
+        let y = graph
+            .node(|input| vec![input[0] * 3.0])
+            .child(x)
+            .unwrap()
+            .build();
+
+        let shared = Arc::new(Mutex::new(graph.clone()));
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let shared = shared.clone();
+                thread::spawn(move || shared.lock().unwrap().compute(y).unwrap().to_vec())
+            })
+            .collect();
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), [6.0]);
+        }
+
+        let output = thread::spawn(move || {
+            graph.input(x).unwrap().set(vec![5.0]);
+            graph.compute(y).unwrap().to_vec()
+        });
+        assert_eq!(output.join().unwrap(), [15.0]);
+    }
+
+    #[test]
+    fn test_connect() {
+        let mut graph = Graph::new();
+        let total = graph.node(|input| vec![input.iter().sum()]).build();
+        let a = graph
+            .node(|input| input)
+            .input(vec![1.0])
+            .feeds(total)
+            .unwrap()
+            .build();
+        let b = graph.node(|input| input).input(vec![2.0]).build();
+        graph.connect(b, total).unwrap();
+        assert_eq!(graph.compute(total).unwrap(), [3.0]);
+
+        graph.input(a).unwrap().set(vec![5.0]);
+        assert_eq!(graph.compute(total).unwrap(), [7.0]);
+        assert_eq!(
+            graph.connect(total, a),
+            Err(GraphError::CycleDetected(vec![a, total, a]))
+        );
+    }
+
+    #[test]
+    fn test_disable() {
+        let mut graph = Graph::new();
+        let x = graph.node(|input| input).input(vec![2.0]).build();
+        let enrich = graph
+            .node(|input| vec![input[0] * 100.0])
+            .child(x)
+            .unwrap()
+            .input(vec![1.0])
+            .build();
+        let total = graph
+            .node(|input| vec![input.iter().sum()])
+            .child(enrich)
+            .unwrap()
+            .build();
+        assert_eq!(graph.compute(total).unwrap(), [200.0]);
+
+        graph.disable(enrich).unwrap();
+        assert!(graph.is_dirty(total).unwrap());
+        assert_eq!(graph.compute(total).unwrap(), [3.0]);
+        assert!(graph.node_info(enrich).unwrap().disabled);
+
+        graph.disable_with(enrich, vec![-1.0]).unwrap();
+        assert_eq!(graph.compute(total).unwrap(), [-1.0]);
+        graph.input(x).unwrap().set(vec![5.0]);
+        assert_eq!(graph.compute(total).unwrap(), [-1.0]);
+
+        graph.enable(enrich).unwrap();
+        assert!(!graph.is_disabled(enrich).unwrap());
+        assert_eq!(graph.compute(total).unwrap(), [500.0]);
+        graph.enable(enrich).unwrap();
+        assert!(!graph.is_dirty(total).unwrap());
+        assert_eq!(
+            graph.disable(NodeId(9)),
+            Err(GraphError::UnknownNode(NodeId(9)))
+        );
+        assert_eq!(
+            graph.is_disabled(NodeId(9)),
+            Err(GraphError::UnknownNode(NodeId(9)))
+        );
+    }
+
+    #[test]
+    fn test_shadow() {
+        let mut graph = Graph::new();
+        let x = graph.node(|input| input).input(vec![2.0]).build();
+        let current = graph
+            .node(|input| vec![input[0] * 2.0])
+            .child(x)
+            .unwrap()
+            .build();
+        let candidate = graph
+            .fallible_node(|input| match input[0] {
+                x if x < 0.0 => Err(NodeError::failed("negative")),
+                x if x > 2.0 => Ok(vec![x * 2.0 + 0.5]),
+                x => Ok(vec![x * 2.0]),
+            })
+            .child(x)
+            .unwrap()
+            .build();
+        let top = graph
+            .node(|input| vec![input[0] + 1.0])
+            .child(current)
+            .unwrap()
+            .in_place()
+            .build();
+        graph
+            .set_shadow(current, candidate, crate::shadow::max_abs_diff)
+            .unwrap();
+
+        assert_eq!(graph.compute(top).unwrap(), [5.0]);
+        graph.input(x).unwrap().set(vec![3.0]);
+        assert_eq!(graph.compute(top).unwrap(), [7.0]);
+        graph.compute(top).unwrap();
+        graph.input(x).unwrap().set(vec![-1.0]);
+        assert_eq!(graph.compute(top).unwrap(), [-1.0]);
+
+        let report = graph.shadow_report(current).unwrap();
+        assert_eq!(report.evaluations, 3);
+        assert_eq!(report.divergences, 1);
+        assert_eq!(report.failures, 1);
+        assert_eq!(report.max_distance, 0.5);
+        assert_eq!(report.mean_distance(), 0.25);
+        assert_eq!(
+            report.last_error,
+            Some(GraphError::Node {
+                node: candidate,
+                message: "negative".to_owned()
+            })
+        );
+
+        graph.input(x).unwrap().set(vec![3.0]);
+        assert_eq!(graph.promote_shadow(current), Ok(candidate));
+        assert_eq!(graph.compute(top).unwrap(), [7.5]);
+        assert_eq!(graph.shadow_report(current), None);
+        assert!(graph.node_info(current).unwrap().parents.is_empty());
+        assert_eq!(
+            graph.promote_shadow(current),
+            Err(GraphError::NoShadow(current))
+        );
+
+        graph.set_shadow(candidate, top, |_, _| 0.0).unwrap();
+        assert!(matches!(
+            graph.promote_shadow(candidate),
+            Err(GraphError::CycleDetected(_))
+        ));
+        assert!(graph.clear_shadow(candidate).is_some());
+    }
+
+    #[test]
+    fn test_traversal() {
+        let mut graph = Graph::new();
+        let x = graph.add_node(|input| input);
+        let y = graph.add_node(|input| input);
+        let left = graph.node(|input| input).child(x).unwrap().build();
+        let right = graph
+            .node(|input| input)
+            .child(y)
+            .unwrap()
+            .child(x)
+            .unwrap()
+            .build();
+        let top = graph
+            .node(|input| input)
+            .child(right)
+            .unwrap()
+            .child(left)
+            .unwrap()
+            .build();
+        let lone = graph.add_node(|input| input);
+        graph.remove_node(lone).unwrap();
+
+        let topo: Vec<_> = graph.iter_topo().collect();
+        assert_eq!(topo, [x, y, left, right, top]);
+        let descendants: Vec<_> = graph.descendants(top).collect();
+        assert_eq!(descendants, [right, left, y, x]);
+        let ancestors: Vec<_> = graph.ancestors(x).collect();
+        assert_eq!(ancestors, [left, right, top]);
+        assert_eq!(graph.descendants(x).count(), 0);
+        assert_eq!(graph.ancestors(lone).count(), 0);
+    }
+
+    #[test]
+    fn test_drop() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct Guard(Arc<AtomicUsize>);
+
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut graph = Graph::new();
+        let add = |graph: &mut Graph, children: &[NodeId]| {
+            let guard = Guard(dropped.clone());
+            let mut builder = graph.node(move |input| {
+                let _ = &guard;
+                input
+            });
+            for child in children {
+                builder = builder.child(*child).unwrap();
+            }
+            builder.build()
+        };
+        // Diamond, so that nodes are reachable through several parents.
+        let x = add(&mut graph, &[]);
+        let left = add(&mut graph, &[x]);
+        let right = add(&mut graph, &[x]);
+        let top = add(&mut graph, &[left, right]);
+        graph.input(x).unwrap().set(vec![1.0]);
+        graph.compute(top).unwrap();
+
+        // Clones share the node functions, which are freed with the last graph holding them.
+        let clone = graph.clone();
+        drop(graph);
+        assert_eq!(dropped.load(Ordering::SeqCst), 0);
+        drop(clone);
+        assert_eq!(dropped.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut graph = Graph::new();
+        let a = graph.node(|input| input).input(vec![1.0]).build();
+        let b = graph.node(|input| input).input(vec![10.0]).build();
+        let sum = graph
+            .node(|input| vec![input.iter().sum()])
+            .child(a)
+            .unwrap()
+            .child(b)
+            .unwrap()
+            .build();
+        let top = graph
+            .node(|input| vec![input[0] * 2.0])
+            .name("top")
+            .unwrap()
+            .child(sum)
+            .unwrap()
+            .build();
+        assert_eq!(graph.compute(top).unwrap(), [22.0]);
+
+        graph.remove_child(sum, b).unwrap();
+        assert!(graph.is_dirty(top).unwrap());
+        assert_eq!(graph.compute(top).unwrap(), [2.0]);
+        assert_eq!(
+            graph.remove_child(sum, b),
+            Err(GraphError::NoEdge {
+                parent: sum,
+                child: b
+            })
+        );
+
+        graph
+            .replace_func(sum, |input| vec![input.iter().sum::<f32>() + 100.0])
+            .unwrap();
+        assert!(graph.is_dirty(top).unwrap());
+        assert_eq!(graph.compute(top).unwrap(), [202.0]);
+
+        graph.add_children(sum, b).unwrap();
+        graph.remove_node(a).unwrap();
+        assert_eq!(graph.compute(top).unwrap(), [220.0]);
+        assert_eq!(graph.compute(a), Err(GraphError::UnknownNode(a)));
+        assert_eq!(
+            graph.set_description(a, "removed"),
+            Err(GraphError::UnknownNode(a))
+        );
+        assert_eq!(graph.len(), 3);
+        assert!(!graph.nodes().any(|node| node == a));
+
+        graph.remove_node(top).unwrap();
+        assert_eq!(graph.node_by_name("top"), None);
+        assert_eq!(
+            graph.add_children(top, sum),
+            Err(GraphError::UnknownNode(top))
+        );
+        assert_eq!(graph.compute(sum).unwrap(), [110.0]);
+    }
+
+    #[test]
+    fn test_incremental() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let full = Arc::new(AtomicUsize::new(0));
+        let counter = full.clone();
+        let mut graph = Graph::new();
+        let data = graph
+            .node(|input| input)
+            .input((0..1000).map(|i| i as f32).collect())
+            .build();
+        graph
+            .set_incremental(data, |output, changes| {
+                for change in changes {
+                    output[change.index] = change.new;
+                }
+                Ok(changes.to_vec())
+            })
+            .unwrap();
+        let sum = graph
+            .node(move |input| {
+                counter.fetch_add(1, Ordering::Relaxed);
+                vec![input.iter().sum()]
+            })
+            .child(data)
+            .unwrap()
+            .input(vec![0.0])
+            .build();
+        graph
+            .set_incremental(sum, |output, changes| {
+                let old = output[0];
+                output[0] += changes.iter().map(|c| c.new - c.old).sum::<f32>();
+                Ok(vec![Change {
+                    index: 0,
+                    old,
+                    new: output[0],
+                }])
+            })
+            .unwrap();
+        let top = graph
+            .node(|input| vec![input[0] * 2.0])
+            .child(sum)
+            .unwrap()
+            .build();
+
+        assert_eq!(graph.compute(top).unwrap(), [999000.0]);
+        assert_eq!(full.load(Ordering::Relaxed), 1);
+
+        graph.input(data).unwrap().update(10, 20.0).unwrap();
+        graph.input(data).unwrap().update(10, 30.0).unwrap();
+        graph.input(sum).unwrap().update(0, 5.0).unwrap();
+        assert_eq!(graph.compute(top).unwrap(), [999050.0]);
+        assert_eq!(full.load(Ordering::Relaxed), 1);
+        assert_eq!(graph.compute(data).unwrap()[10], 30.0);
+
+        assert_eq!(graph.input(data).unwrap().update(1000, 0.0), None);
+        graph.input(data).unwrap().set(vec![1.0, 2.0]);
+        assert_eq!(graph.compute(top).unwrap(), [16.0]);
+        assert_eq!(full.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            graph.set_incremental(NodeId(9), |_, _| Ok(vec![])),
+            Err(GraphError::UnknownNode(NodeId(9)))
+        );
+    }
+
+    #[test]
+    fn test_ports() {
+        let mut graph = Graph::new();
+        let a = graph.node(|input| input).input(vec![10.0]).build();
+        let b = graph.node(|input| input).input(vec![4.0]).build();
+        let c = graph.node(|input| input).input(vec![1.0]).build();
+        let sub = graph
+            .port_node(&["lhs", "rhs"], |inputs| {
+                Ok(vec![inputs.port("lhs")?[0] - inputs.port("rhs")?[0]])
+            })
+            .port("rhs", b)
+            .unwrap()
+            .port("lhs", a)
+            .unwrap()
+            .build();
+        assert_eq!(graph.compute(sub).unwrap(), [6.0]);
+
+        graph.connect_port(sub, "lhs", c).unwrap();
+        assert_eq!(graph.compute(sub).unwrap(), [-3.0]);
+        assert_eq!(graph.inner(sub).down, [b, c]);
+
+        graph.remove_child(sub, b).unwrap();
+        assert_eq!(
+            graph.compute(sub),
+            Err(GraphError::MissingPort {
+                node: sub,
+                port: "rhs".to_owned()
+            })
+        );
+        assert_eq!(
+            graph.connect_port(sub, "base", a),
+            Err(GraphError::UnknownPort {
+                node: sub,
+                port: "base".to_owned()
+            })
+        );
+        graph.connect_port(sub, "rhs", c).unwrap();
+        graph.connect_port(sub, "lhs", a).unwrap();
+        assert_eq!(graph.compute(sub).unwrap(), [9.0]);
+        assert_eq!(graph.inner(sub).down, [c, a]);
+    }
+
+    #[test]
+    fn test_arity() {
+        let mut graph = Graph::new();
+        let a = graph.node(|input| input).input(vec![1.0]).build();
+        let b = graph.node(|input| input).input(vec![2.0]).build();
+        let add = graph
+            .node(|input| vec![input[0] + input[1]])
+            .arity(2)
+            .child(a)
+            .unwrap()
+            .build();
+        let sub = graph
+            .port_node(&["lhs", "rhs"], |inputs| {
+                Ok(vec![inputs.port("lhs")?[0] - inputs.port("rhs")?[0]])
+            })
+            .port("lhs", add)
+            .unwrap()
+            .build();
+
+        assert_eq!(
+            graph.validate(),
+            Err(vec![
+                GraphError::UnderConnected {
+                    node: add,
+                    expected: 2,
+                    actual: 1
+                },
+                GraphError::MissingPort {
+                    node: sub,
+                    port: "rhs".to_owned()
+                },
+            ])
+        );
+
+        graph.add_children(add, b).unwrap();
+        assert_eq!(
+            graph.add_children(add, b),
+            Err(GraphError::ArityExceeded {
+                node: add,
+                arity: 2
+            })
+        );
+        graph.connect_port(sub, "rhs", b).unwrap();
+        assert_eq!(graph.validate(), Ok(()));
+        assert_eq!(graph.compute(sub).unwrap(), [1.0]);
+        assert_eq!(
+            graph.set_arity(NodeId(9), 1),
+            Err(GraphError::UnknownNode(NodeId(9)))
+        );
+
+        // Rebinding a port of a node at its arity replaces the child instead of adding one.
+        let negate = graph
+            .port_node(&["x"], |inputs| Ok(vec![-inputs.port("x")?[0]]))
+            .arity(1)
+            .port("x", a)
+            .unwrap()
+            .build();
+        graph.connect_port(negate, "x", b).unwrap();
+        assert_eq!(graph.compute(negate).unwrap(), [-2.0]);
+        assert_eq!(
+            graph.add_children(negate, a),
+            Err(GraphError::ArityExceeded {
+                node: negate,
+                arity: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_rng() {
+        let mut graph = Graph::new();
+        let draws: Vec<_> = (0..2)
+            .map(|_| {
+                graph
+                    .contextual_node(|_, context| Ok(context.rng().take(2).collect()))
+                    .impure()
+                    .build()
+            })
+            .collect();
+        let total = graph
+            .node(|input| input)
+            .child(draws[0])
+            .unwrap()
+            .child(draws[1])
+            .unwrap()
+            .build();
+
+        graph.set_seed(7);
+        let first = graph.compute(total).unwrap().to_vec();
+        let second = graph.compute(total).unwrap().to_vec();
+        assert_ne!(first[..2], first[2..]);
+        assert_ne!(first, second);
+
+        graph.set_seed(7);
+        assert_eq!(graph.compute(total).unwrap(), first);
+        graph.set_seed(8);
+        assert_ne!(graph.compute(total).unwrap(), first);
+    }
+
+    #[test]
+    fn test_sampling() {
+        let mut graph = Graph::new();
+        let antithetic = graph
+            .contextual_node(|_, context| Ok(context.sampler().take(3).collect()))
+            .sampling(SamplingMode::Antithetic)
+            .impure()
+            .build();
+        // More draws than Sobol dimensions, the last ones are pseudo-random.
+        let sobol = graph
+            .contextual_node(|_, context| Ok(context.sampler().take(12).collect()))
+            .impure()
+            .build();
+        graph.set_sampling(sobol, SamplingMode::Sobol).unwrap();
+
+        // The first evaluation is numbered 1, so the second and third form a pair.
+        graph.set_seed(3);
+        graph.compute(antithetic).unwrap();
+        let even = graph.compute(antithetic).unwrap().to_vec();
+        let odd = graph.compute(antithetic).unwrap().to_vec();
+        assert!(even.iter().zip(&odd).all(|(u, v)| u + v == 1.0));
+
+        graph.set_seed(3);
+        let expected: Vec<f32> = Sampler::new(SamplingMode::Sobol, 3, sobol.0 as u64, 1)
+            .take(12)
+            .collect();
+        assert_eq!(graph.compute(sobol).unwrap(), expected);
+        assert_eq!(
+            graph.set_sampling(NodeId(9), SamplingMode::Halton),
+            Err(GraphError::UnknownNode(NodeId(9)))
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+// Ring buffers of the latest outputs and external inputs of a node, oldest first.
+#[derive(Clone)]
+pub(crate) struct History<T> {
+    capacity: usize,
+    outputs: VecDeque<(SystemTime, Arc<Vec<T>>)>,
+    inputs: VecDeque<(SystemTime, Option<Vec<T>>)>,
+}
+
+impl<T: Clone> History<T> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            outputs: VecDeque::with_capacity(capacity),
+            inputs: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn push_output(&mut self, time: SystemTime, output: Arc<Vec<T>>) {
+        push(&mut self.outputs, self.capacity, (time, output));
+    }
+
+    pub(crate) fn push_input(&mut self, time: SystemTime, input: Option<&[T]>) {
+        push(
+            &mut self.inputs,
+            self.capacity,
+            (time, input.map(<[T]>::to_vec)),
+        );
+    }
+
+    pub(crate) fn outputs(&self) -> impl Iterator<Item = (SystemTime, &[T])> {
+        self.outputs
+            .iter()
+            .map(|(time, output)| (*time, output.as_slice()))
+    }
+
+    // The input that was current at `time`, `None` if it was set before the oldest entry kept.
+    pub(crate) fn input_at(&self, time: SystemTime) -> Option<Option<&[T]>> {
+        self.inputs
+            .iter()
+            .rev()
+            .find(|(recorded, _)| *recorded <= time)
+            .map(|(_, input)| input.as_deref())
+    }
+}
+
+fn push<E>(entries: &mut VecDeque<E>, capacity: usize, entry: E) {
+    if entries.len() == capacity {
+        entries.pop_front();
+    }
+    entries.push_back(entry);
+}
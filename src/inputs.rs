@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use crate::error::NodeError;
+use crate::value::Value;
+use crate::view::OutputView;
+
+/// Inputs of a node with named ports, see [`Graph::port_node`](crate::Graph::port_node).
+pub struct Inputs<'a, T = f32> {
+    pub(crate) ports: Vec<(&'a str, Option<&'a [T]>)>,
+    pub(crate) input: Option<&'a [T]>,
+}
+
+impl<'a, T> Inputs<'a, T> {
+    /// Output of the child connected to `port`, `None` if the port is not connected.
+    pub fn get(&self, port: &str) -> Option<&'a [T]> {
+        self.ports
+            .iter()
+            .find(|(name, _)| *name == port)
+            .and_then(|(_, output)| *output)
+    }
+
+    /// Like [`Inputs::get`], failing with [`NodeError::MissingPort`] for unconnected ports.
+    pub fn port(&self, port: &str) -> Result<&'a [T], NodeError> {
+        self.get(port)
+            .ok_or_else(|| NodeError::MissingPort(port.to_owned()))
+    }
+
+    /// External input of the node.
+    pub fn input(&self) -> Option<&'a [T]> {
+        self.input
+    }
+}
+
+impl<'a, T: Value> Inputs<'a, T> {
+    /// Like [`Inputs::port`], as a view that reads the child's output without copying it.
+    pub fn view(&self, port: &str) -> Result<OutputView<'a, T>, NodeError> {
+        self.port(port).map(OutputView::new)
+    }
+}
+
+pub(crate) type PortFn<T> = Arc<dyn Fn(&Inputs<T>) -> Result<Vec<T>, NodeError> + Send + Sync>;
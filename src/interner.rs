@@ -0,0 +1,98 @@
+//! Sharing of identical node outputs.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+
+/// Turns freshly computed outputs into shared buffers. Returning an existing buffer for a value
+/// equal to one seen before lets identical outputs share one allocation, and lets callers detect
+/// equality with [`Arc::ptr_eq`].
+pub trait Interner<T> {
+    fn intern(&mut self, value: Vec<T>) -> Arc<Vec<T>>;
+}
+
+/// Interner for `f32` graphs keyed by the bit patterns of the values. Only values up to
+/// `max_len` elements are interned, longer ones are too expensive to hash and unlikely to repeat.
+pub struct HashInterner {
+    max_len: usize,
+    values: HashMap<Vec<u32>, Weak<Vec<f32>>>,
+    // Dead entries are purged whenever the table grows past this size.
+    purge_at: usize,
+}
+
+impl HashInterner {
+    pub fn new() -> Self {
+        Self::with_max_len(64)
+    }
+
+    pub fn with_max_len(max_len: usize) -> Self {
+        Self {
+            max_len,
+            values: HashMap::new(),
+            purge_at: 64,
+        }
+    }
+
+    /// Number of distinct values currently held.
+    pub fn len(&self) -> usize {
+        self.values
+            .values()
+            .filter(|value| value.strong_count() > 0)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for HashInterner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interner<f32> for HashInterner {
+    fn intern(&mut self, value: Vec<f32>) -> Arc<Vec<f32>> {
+        if value.len() > self.max_len {
+            return Arc::new(value);
+        }
+
+        let key: Vec<u32> = value.iter().map(|x| x.to_bits()).collect();
+        if let Some(shared) = self.values.get(&key).and_then(Weak::upgrade) {
+            return shared;
+        }
+
+        if self.values.len() >= self.purge_at {
+            self.values.retain(|_, value| value.strong_count() > 0);
+            self.purge_at = (self.values.len() * 2).max(64);
+        }
+
+        let shared = Arc::new(value);
+        self.values.insert(key, Arc::downgrade(&shared));
+        shared
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hash_interner() {
+        let mut interner = HashInterner::with_max_len(2);
+
+        let a = interner.intern(vec![1.0, 0.0]);
+        let b = interner.intern(vec![1.0, 0.0]);
+        let c = interner.intern(vec![0.0, 1.0]);
+        let d = interner.intern(vec![0.0, 0.0, 0.0]);
+        let e = interner.intern(vec![0.0, 0.0, 0.0]);
+
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &c));
+        assert!(!Arc::ptr_eq(&d, &e));
+        assert_eq!(interner.len(), 2);
+
+        drop(c);
+        assert_eq!(interner.len(), 1);
+    }
+}
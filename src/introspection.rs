@@ -0,0 +1,121 @@
+//! Machine-readable description of the nodes of a graph.
+
+use crate::graph::Graph;
+use crate::node::NodeId;
+use crate::rng::SamplingMode;
+use crate::value::Value;
+
+/// Structure and settings of one node, see [`Graph::node_info`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct NodeInfo {
+    pub id: usize,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// Registry key of the op, `None` for nodes built from a closure.
+    pub op: Option<String>,
+    /// Nodes whose outputs are passed to the function, in order.
+    pub children: Vec<usize>,
+    pub parents: Vec<usize>,
+    /// Named input ports, empty for nodes taking a flattened input.
+    pub ports: Vec<String>,
+    /// Declared number of children, see [`Graph::set_arity`].
+    pub arity: Option<usize>,
+    /// Number of external input values, `None` if the node has no external input.
+    pub input_len: Option<usize>,
+    /// Number of values in the cached output, `None` if it is not up to date.
+    pub output_len: Option<usize>,
+    pub disabled: bool,
+    pub in_place: bool,
+    pub sensitive: bool,
+    pub pure: bool,
+    pub sampling: SamplingMode,
+    pub capabilities: Vec<String>,
+}
+
+impl<T: Value> Graph<T> {
+    pub fn node_info(&self, node: NodeId) -> Option<NodeInfo> {
+        let inner = self.get(node).ok()?;
+        Some(NodeInfo {
+            id: node.0,
+            name: inner.name.clone(),
+            description: inner.description.clone(),
+            op: inner.op.clone(),
+            children: inner.down.iter().map(|child| child.0).collect(),
+            parents: inner.up.iter().map(|parent| parent.0).collect(),
+            ports: inner.ports.iter().map(|(port, _)| port.clone()).collect(),
+            arity: inner.arity,
+            input_len: inner.input.as_ref().map(Vec::len),
+            output_len: match inner.is_dirty() {
+                true => None,
+                false => inner.output().map(<[T]>::len),
+            },
+            disabled: inner.disabled.is_some(),
+            in_place: inner.in_place,
+            sensitive: inner.sensitive,
+            pure: inner.pure,
+            sampling: inner.sampling,
+            capabilities: inner.capabilities.clone(),
+        })
+    }
+
+    /// [`NodeInfo`] of every node, indexed by id.
+    pub fn schema(&self) -> Vec<NodeInfo> {
+        self.nodes()
+            .filter_map(|node| self.node_info(node))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::registry::OpRegistry;
+
+    #[test]
+    fn test_node_info() {
+        let mut registry = OpRegistry::new();
+        registry.register("sum", |input: Vec<f32>| Ok(vec![input.iter().sum()]));
+
+        let mut graph = Graph::new();
+        let x = graph.node(|input| input).input(vec![1.0, 2.0]).build();
+        let total = graph
+            .registered_node(&registry, "sum")
+            .unwrap()
+            .name("total")
+            .unwrap()
+            .child(x)
+            .unwrap()
+            .sensitive()
+            .description("sum of x")
+            .build();
+        graph.compute(total).unwrap();
+
+        let schema = graph.schema();
+        assert_eq!(schema.len(), 2);
+        assert_eq!(schema[0].parents, [1]);
+        assert_eq!(schema[0].input_len, Some(2));
+        assert_eq!(
+            schema[1],
+            NodeInfo {
+                id: 1,
+                name: Some("total".to_owned()),
+                description: Some("sum of x".to_owned()),
+                op: Some("sum".to_owned()),
+                children: vec![0],
+                parents: vec![],
+                ports: vec![],
+                arity: None,
+                input_len: None,
+                output_len: Some(1),
+                disabled: false,
+                in_place: false,
+                sensitive: true,
+                pure: true,
+                sampling: SamplingMode::Pseudo,
+                capabilities: vec![],
+            }
+        );
+        assert_eq!(graph.node_info(NodeId(2)), None);
+    }
+}
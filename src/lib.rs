@@ -0,0 +1,42 @@
+//! Lazily evaluated computation graph over vectors of values, `f32` by default.
+//!
+//! Nodes cache their output and recompute only after an input or the wiring below them changes.
+
+mod context;
+mod delta;
+#[cfg(feature = "dot")]
+mod dot;
+pub mod dual;
+mod error;
+pub mod expr;
+pub mod format;
+mod graph;
+mod history;
+mod inputs;
+pub mod interner;
+pub mod introspection;
+mod macros;
+mod node;
+pub mod ops;
+pub mod optimize;
+#[cfg(feature = "rayon")]
+mod rayon;
+pub mod registry;
+pub mod rng;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod shadow;
+mod sweep;
+pub mod testing;
+mod value;
+mod view;
+
+pub use context::EvalContext;
+pub use delta::Change;
+pub use error::{GraphError, NodeError};
+pub use graph::{Graph, Input, NodeBuilder};
+pub use inputs::Inputs;
+pub use node::NodeId;
+pub use sweep::SweepRow;
+pub use value::Value;
+pub use view::OutputView;
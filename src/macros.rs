@@ -0,0 +1,85 @@
+/// Declares nodes of a graph and their wiring, binding each node's id to a variable of the same
+/// name. Every statement adds one node, children must be declared before their parents:
+///
+/// - `name = input[values];` adds a node passing its external input through.
+/// - `name = op => (children), [values];` adds a node running the fallible function `op`, such
+///   as one from [`ops`](crate::ops), with the given children and external input. Both are
+///   optional.
+///
+/// Wiring errors are returned with `?`, so the macro must be used in a function returning
+/// `Result<_, GraphError>`.
+///
+/// ```
+/// use computation_graph::{graph, ops, Graph, GraphError};
+///
+/// fn main() -> Result<(), GraphError> {
+///     let mut g = Graph::new();
+///     graph! { g;
+///         x = input[3.0];
+///         y = ops::add() => (x), [2.0];
+///         z = ops::mul() => (x, y);
+///     }
+///     assert_eq!(g.compute(z)?, [15.0]);
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! graph {
+    ($graph:ident; $($statements:tt)*) => {
+        $crate::graph!(@statements $graph; $($statements)*);
+    };
+    (@statements $graph:ident;) => {};
+    (@statements $graph:ident;
+        $name:ident = input [$($value:expr),* $(,)?];
+        $($rest:tt)*
+    ) => {
+        let $name = $graph.node(|input| input).input(vec![$($value),*]).build();
+        $crate::graph!(@statements $graph; $($rest)*);
+    };
+    (@statements $graph:ident;
+        $name:ident = $op:expr $(=> ($($child:ident),* $(,)?))? $(, [$($value:expr),* $(,)?])?;
+        $($rest:tt)*
+    ) => {
+        let $name = $graph
+            .fallible_node($op)
+            $($(.child($child)?)*)?
+            $(.input(vec![$($value),*]))?
+            .build();
+        $crate::graph!(@statements $graph; $($rest)*);
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::GraphError;
+    use crate::graph::Graph;
+    use crate::ops;
+
+    #[test]
+    fn test_graph_macro() -> Result<(), GraphError> {
+        let mut graph = Graph::new();
+        graph! { graph;
+            x = input[3.0];
+            cube = ops::powf(3.0) => (x);
+            shifted = ops::add() => (cube), [2.0];
+            scaled = |input: Vec<f32>| Ok(vec![input[0] * 2.0]) => (shifted,);
+            constant = ops::constant(vec![1.0, 2.0]);
+        }
+        assert_eq!(graph.compute(scaled)?, [58.0]);
+        assert_eq!(graph.compute(constant)?, [1.0, 2.0]);
+
+        graph.input(x).unwrap().set(vec![1.0]);
+        assert_eq!(graph.compute(scaled)?, [6.0]);
+
+        let removed = graph.add_node(|input| input);
+        graph.remove_node(removed)?;
+        let result = (|| -> Result<_, GraphError> {
+            graph! { graph;
+                orphan = ops::identity() => (removed);
+            }
+            Ok(orphan)
+        })();
+        assert_eq!(result, Err(GraphError::UnknownNode(removed)));
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::context::EvalContext;
+use crate::delta::{Change, UpdateFn};
+use crate::error::NodeError;
+use crate::format::Format;
+use crate::history::History;
+use crate::inputs::PortFn;
+use crate::rng::SamplingMode;
+use crate::shadow::Shadow;
+use crate::value::Value;
+
+/// Index of a node inside the [`Graph`](crate::Graph) that created it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub(crate) usize);
+
+impl NodeId {
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+// Shared so that cloning a graph does not require cloning the closures it holds.
+pub(crate) type NodeFn<T> =
+    Arc<dyn Fn(Vec<T>, &EvalContext<T>) -> Result<Vec<T>, NodeError> + Send + Sync>;
+
+// Output of a disabled node in place of its function.
+#[derive(Clone)]
+pub(crate) enum Bypass<T> {
+    // The flattened input, children outputs followed by the external input.
+    Identity,
+    Fallback(Vec<T>),
+}
+
+#[derive(Clone)]
+pub(crate) struct NodeInner<T> {
+    pub(crate) name: Option<String>,
+    // Human-readable account of what the node computes, for reports and exports.
+    pub(crate) description: Option<String>,
+    // How the output is rendered by `Graph::render`.
+    pub(crate) format: Option<Format>,
+    // Instead Vec we can use HashMap to exclude duplication and better handle relationship.
+    pub(crate) up: Vec<NodeId>,
+    pub(crate) down: Vec<NodeId>,
+    // Instead this function signature we can use fn(f32, f32) -> f32 that exclude handling existence of the element,
+    // but then we need more nodes for cases with multiply inputs,outputs.
+    // `None` for placeholders whose op has not been resolved yet.
+    pub(crate) func: Option<NodeFn<T>>,
+    // Function of nodes with named ports, which take the place of `func`.
+    pub(crate) port_func: Option<PortFn<T>>,
+    // Declared ports with the child connected to each, the children are also listed in `down`.
+    pub(crate) ports: Vec<(String, Option<NodeId>)>,
+    // Number of children the node accepts, unlimited when `None`.
+    pub(crate) arity: Option<usize>,
+    // Incremental counterpart of `func`, the output of a dirty node that has one is kept so it
+    // can be updated in place.
+    pub(crate) update: Option<UpdateFn<T>>,
+    // Changes to the flattened input since the last evaluation, `None` when they are not known.
+    pub(crate) pending: Option<Vec<Change<T>>>,
+    // Registry key of the function, nodes without one cannot be serialized.
+    pub(crate) op: Option<String>,
+    pub(crate) cache: Option<Arc<Vec<T>>>,
+    pub(crate) input: Option<Vec<T>>,
+    // Set when an input or the wiring below the node changed since its last evaluation. A dirty
+    // node only has dirty ancestors.
+    pub(crate) dirty: bool,
+    pub(crate) disabled: Option<Bypass<T>>,
+    pub(crate) in_place: bool,
+    pub(crate) sensitive: bool,
+    pub(crate) pure: bool,
+    // Set when the node or any of its descendants is impure.
+    pub(crate) volatile: bool,
+    // Capabilities an evaluation must hold to request this node's output.
+    pub(crate) capabilities: Vec<String>,
+    pub(crate) sampling: SamplingMode,
+    pub(crate) history: Option<History<T>>,
+    pub(crate) shadow: Option<Shadow<T>>,
+    // Tombstone left by `Graph::remove_node`, so that the ids of other nodes stay valid.
+    pub(crate) removed: bool,
+}
+
+impl<T: Value> NodeInner<T> {
+    pub(crate) fn new(func: Option<NodeFn<T>>) -> Self {
+        Self {
+            name: None,
+            description: None,
+            format: None,
+            up: vec![],
+            down: vec![],
+            func,
+            port_func: None,
+            ports: vec![],
+            arity: None,
+            update: None,
+            pending: None,
+            op: None,
+            cache: None,
+            input: None,
+            dirty: true,
+            disabled: None,
+            in_place: false,
+            sensitive: false,
+            pure: true,
+            volatile: false,
+            capabilities: vec![],
+            sampling: SamplingMode::default(),
+            history: None,
+            shadow: None,
+            removed: false,
+        }
+    }
+
+    // Whether the node must be recomputed before its output can be served.
+    pub(crate) fn is_dirty(&self) -> bool {
+        self.dirty || self.volatile || self.cache.is_none()
+    }
+
+    pub(crate) fn output(&self) -> Option<&[T]> {
+        self.cache.as_deref().map(Vec::as_slice)
+    }
+
+    // The buffer is moved out when this node is its only owner and copied when it is interned.
+    pub(crate) fn take_output(&mut self) -> Option<Vec<T>> {
+        let res = self.cache.take()?;
+        Some(Arc::try_unwrap(res).unwrap_or_else(|shared| shared.as_ref().clone()))
+    }
+
+    // Formats a value of this node for diagnostics, honoring the sensitivity flag.
+    pub(crate) fn display_value(&self, value: &[T]) -> String {
+        if self.sensitive {
+            "<redacted>".to_owned()
+        } else {
+            format!("{:?}", value)
+        }
+    }
+}
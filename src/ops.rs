@@ -0,0 +1,175 @@
+//! Ready-made node functions for graphs of [`Scalar`] values, to be passed to
+//! [`Graph::fallible_node`].
+//!
+//! Functions with a fixed number of inputs check it and report missing or extra values as
+//! [`NodeError::ArityMismatch`].
+//!
+//! [`Graph::fallible_node`]: crate::Graph::fallible_node
+
+use std::ops::{Add, Div, Mul, Sub};
+
+use crate::error::NodeError;
+use crate::value::Value;
+
+/// Number type the built-in ops compute with. Besides floats it is implemented by
+/// [`Dual`](crate::dual::Dual) to differentiate graphs built from these ops.
+pub trait Scalar:
+    Value + Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self>
+{
+    fn zero() -> Self;
+    fn sin(self) -> Self;
+    fn powf(self, n: f32) -> Self;
+}
+
+impl Scalar for f32 {
+    fn zero() -> Self {
+        0.0
+    }
+
+    fn sin(self) -> Self {
+        f32::sin(self)
+    }
+
+    fn powf(self, n: f32) -> Self {
+        f32::powf(self, n)
+    }
+}
+
+impl Scalar for f64 {
+    fn zero() -> Self {
+        0.0
+    }
+
+    fn sin(self) -> Self {
+        f64::sin(self)
+    }
+
+    fn powf(self, n: f32) -> Self {
+        f64::powf(self, n.into())
+    }
+}
+
+fn arity<S>(input: &[S], expected: usize) -> Result<(), NodeError> {
+    if input.len() == expected {
+        Ok(())
+    } else {
+        Err(NodeError::ArityMismatch {
+            expected,
+            actual: input.len(),
+        })
+    }
+}
+
+/// Sum of two values.
+pub fn add<S: Scalar>() -> impl Fn(Vec<S>) -> Result<Vec<S>, NodeError> {
+    |input| {
+        arity(&input, 2)?;
+        Ok(vec![input[0] + input[1]])
+    }
+}
+
+/// Difference of two values, the second subtracted from the first.
+pub fn sub<S: Scalar>() -> impl Fn(Vec<S>) -> Result<Vec<S>, NodeError> {
+    |input| {
+        arity(&input, 2)?;
+        Ok(vec![input[0] - input[1]])
+    }
+}
+
+/// Product of two values.
+pub fn mul<S: Scalar>() -> impl Fn(Vec<S>) -> Result<Vec<S>, NodeError> {
+    |input| {
+        arity(&input, 2)?;
+        Ok(vec![input[0] * input[1]])
+    }
+}
+
+/// Quotient of two values, the first divided by the second.
+pub fn div<S: Scalar>() -> impl Fn(Vec<S>) -> Result<Vec<S>, NodeError> {
+    |input| {
+        arity(&input, 2)?;
+        Ok(vec![input[0] / input[1]])
+    }
+}
+
+/// Sine of one value.
+pub fn sin<S: Scalar>() -> impl Fn(Vec<S>) -> Result<Vec<S>, NodeError> {
+    |input| {
+        arity(&input, 1)?;
+        Ok(vec![input[0].sin()])
+    }
+}
+
+/// One value raised to the power `n`.
+pub fn powf<S: Scalar>(n: f32) -> impl Fn(Vec<S>) -> Result<Vec<S>, NodeError> {
+    move |input| {
+        arity(&input, 1)?;
+        Ok(vec![input[0].powf(n)])
+    }
+}
+
+/// Sum of any number of values, zero for none.
+pub fn sum<S: Scalar>() -> impl Fn(Vec<S>) -> Result<Vec<S>, NodeError> {
+    |input| Ok(vec![input.into_iter().fold(S::zero(), S::add)])
+}
+
+/// Passes its input through unchanged.
+pub fn identity<S: Scalar>() -> impl Fn(Vec<S>) -> Result<Vec<S>, NodeError> {
+    Ok
+}
+
+/// Always produces `value`, takes no inputs.
+pub fn constant<S: Scalar>(value: Vec<S>) -> impl Fn(Vec<S>) -> Result<Vec<S>, NodeError> {
+    move |input| {
+        arity(&input, 0)?;
+        Ok(value.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Graph, GraphError};
+
+    #[test]
+    fn test_ops() {
+        assert_eq!(add()(vec![1.0, 2.0]), Ok(vec![3.0]));
+        assert_eq!(mul()(vec![3.0, 2.0]), Ok(vec![6.0]));
+        assert_eq!(sub()(vec![3.0, 2.0]), Ok(vec![1.0]));
+        assert_eq!(div()(vec![3.0, 2.0]), Ok(vec![1.5]));
+        assert_eq!(sin()(vec![0.0]), Ok(vec![0.0]));
+        assert_eq!(powf(3.0)(vec![2.0]), Ok(vec![8.0]));
+        assert_eq!(sum()(vec![1.0, 2.0, 3.0]), Ok(vec![6.0]));
+        assert_eq!(sum()(vec![]), Ok(vec![0.0]));
+        assert_eq!(identity()(vec![1.0, 2.0]), Ok(vec![1.0, 2.0]));
+        assert_eq!(constant(vec![4.0])(vec![]), Ok(vec![4.0]));
+    }
+
+    #[test]
+    fn test_arity() {
+        let mut graph = Graph::new();
+        let x = graph.fallible_node(sin()).input(vec![1.0, 2.0]).build();
+        let y = graph.fallible_node(add()).child(x).unwrap().build();
+
+        assert_eq!(
+            graph.compute(y),
+            Err(GraphError::ArityMismatch {
+                node: x,
+                expected: 1,
+                actual: 2
+            })
+        );
+        graph.input(x).unwrap().set(vec![0.0]);
+        assert_eq!(
+            graph.compute(y).unwrap_err().to_string(),
+            "node #1 expects 2 inputs, got 1"
+        );
+        assert_eq!(
+            constant(vec![1.0])(vec![2.0]),
+            Err(NodeError::ArityMismatch {
+                expected: 0,
+                actual: 1
+            })
+        );
+    }
+}
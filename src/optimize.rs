@@ -0,0 +1,338 @@
+//! Derivative-free search for the inputs that minimize or maximize the output of a node.
+//!
+//! Each searched input is a single value within bounds, set as the whole external input of its
+//! node. The objective is the first value of the output of a sink node. Every search leaves the
+//! searched nodes with the best inputs it found.
+
+use std::cmp::Ordering;
+
+use crate::error::GraphError;
+use crate::graph::Graph;
+use crate::node::NodeId;
+use crate::rng::RngStream;
+
+/// Whether the objective should be made as small or as large as possible.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Goal {
+    Minimize,
+    Maximize,
+}
+
+/// Input searched between `min` and `max`, both inclusive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Param {
+    pub node: NodeId,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Param {
+    pub fn new(node: NodeId, min: f32, max: f32) -> Self {
+        Self { node, min, max }
+    }
+}
+
+/// Inputs found by a search, in the order of the searched params, and the objective they reach.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Optimum {
+    pub inputs: Vec<f32>,
+    pub value: f32,
+}
+
+impl Goal {
+    // Orders `a` before `b` when it is the better objective, NaN being the worst.
+    fn compare(self, a: f32, b: f32) -> Ordering {
+        match (a.is_nan(), b.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => match self {
+                Goal::Minimize => a.total_cmp(&b),
+                Goal::Maximize => b.total_cmp(&a),
+            },
+        }
+    }
+}
+
+impl Graph {
+    /// Evaluates a grid of `points` values per param, then repeats `rounds - 1` times on a grid
+    /// narrowed around the best point to the spacing of the previous one. Each grid is evaluated
+    /// with [`Graph::sweep`].
+    pub fn grid_refine(
+        &mut self,
+        params: &[Param],
+        sink: NodeId,
+        goal: Goal,
+        points: usize,
+        rounds: usize,
+    ) -> Result<Optimum, GraphError> {
+        let points = points.max(2);
+        let mut bounds: Vec<(f32, f32)> = params.iter().map(|p| (p.min, p.max)).collect();
+        let mut best: Option<Optimum> = None;
+        for _ in 0..rounds.max(1) {
+            let grid: Vec<_> = params
+                .iter()
+                .zip(bounds.iter())
+                .map(|(param, (min, max))| {
+                    let values = (0..points)
+                        .map(|i| vec![min + (max - min) * i as f32 / (points - 1) as f32])
+                        .collect();
+                    (param.node, values)
+                })
+                .collect();
+            for row in self.sweep(&grid, &[sink])? {
+                let candidate = Optimum {
+                    inputs: row.params.iter().map(|input| input[0]).collect(),
+                    value: objective(sink, &row.outputs[0])?,
+                };
+                if best
+                    .as_ref()
+                    .is_none_or(|best| goal.compare(candidate.value, best.value).is_lt())
+                {
+                    best = Some(candidate);
+                }
+            }
+            let center = &best.as_ref().ok_or(GraphError::NotComputed(sink))?.inputs;
+            for ((bound, param), x) in bounds.iter_mut().zip(params).zip(center) {
+                let step = (bound.1 - bound.0) / (points - 1) as f32;
+                *bound = ((x - step).max(param.min), (x + step).min(param.max));
+            }
+        }
+        let best = best.ok_or(GraphError::NotComputed(sink))?;
+        self.set_inputs(params, &best.inputs)?;
+        Ok(best)
+    }
+
+    /// Evaluates `samples` uniformly random points drawn from the stream `seed` and returns the
+    /// best `k`, best first. The inputs are left at the best point even when `k` is zero.
+    pub fn random_search(
+        &mut self,
+        params: &[Param],
+        sink: NodeId,
+        goal: Goal,
+        samples: usize,
+        seed: u64,
+        k: usize,
+    ) -> Result<Vec<Optimum>, GraphError> {
+        let mut rng = RngStream::new(seed, 0, 0);
+        let mut found = Vec::with_capacity(samples);
+        for _ in 0..samples {
+            let inputs: Vec<f32> = params
+                .iter()
+                .map(|p| p.min + (p.max - p.min) * rng.next_f32())
+                .collect();
+            let value = self.evaluate_at(params, sink, &inputs)?;
+            found.push(Optimum { inputs, value });
+        }
+        found.sort_by(|a, b| goal.compare(a.value, b.value));
+        if let Some(best) = found.first() {
+            self.set_inputs(params, &best.inputs)?;
+        }
+        found.truncate(k);
+        Ok(found)
+    }
+
+    /// Nelder–Mead simplex search started from the center of the bounds, running `iterations`
+    /// reflection steps. Points are clamped to the bounds.
+    pub fn nelder_mead(
+        &mut self,
+        params: &[Param],
+        sink: NodeId,
+        goal: Goal,
+        iterations: usize,
+    ) -> Result<Optimum, GraphError> {
+        let clamp = |point: Vec<f32>| -> Vec<f32> {
+            point
+                .into_iter()
+                .zip(params)
+                .map(|(x, p)| x.clamp(p.min, p.max))
+                .collect()
+        };
+        let center: Vec<f32> = params.iter().map(|p| (p.min + p.max) / 2.0).collect();
+        let mut simplex = vec![center.clone()];
+        for (i, param) in params.iter().enumerate() {
+            let mut vertex = center.clone();
+            vertex[i] += (param.max - param.min) / 4.0;
+            simplex.push(vertex);
+        }
+        let mut simplex = simplex
+            .into_iter()
+            .map(|inputs| {
+                let value = self.evaluate_at(params, sink, &inputs)?;
+                Ok(Optimum { inputs, value })
+            })
+            .collect::<Result<Vec<_>, GraphError>>()?;
+
+        // Point at `from + t * (to - from)`.
+        let towards = |from: &[f32], to: &[f32], t: f32| -> Vec<f32> {
+            clamp(from.iter().zip(to).map(|(a, b)| a + t * (b - a)).collect())
+        };
+        // A single vertex has nowhere to move.
+        let iterations = if params.is_empty() { 0 } else { iterations };
+        for _ in 0..iterations {
+            simplex.sort_by(|a, b| goal.compare(a.value, b.value));
+            let (worst, rest) = simplex.split_last().expect("simplex has n + 1 vertices");
+            let mut centroid = vec![0.0; params.len()];
+            for vertex in rest {
+                for (c, x) in centroid.iter_mut().zip(vertex.inputs.iter()) {
+                    *c += x / rest.len() as f32;
+                }
+            }
+
+            let reflected = towards(&worst.inputs, &centroid, 2.0);
+            let reflected_value = self.evaluate_at(params, sink, &reflected)?;
+            let candidate = if goal.compare(reflected_value, rest[0].value).is_lt() {
+                let expanded = towards(&worst.inputs, &centroid, 3.0);
+                let expanded_value = self.evaluate_at(params, sink, &expanded)?;
+                match goal.compare(expanded_value, reflected_value).is_lt() {
+                    true => Some((expanded, expanded_value)),
+                    false => Some((reflected, reflected_value)),
+                }
+            } else if goal
+                .compare(reflected_value, rest[rest.len() - 1].value)
+                .is_lt()
+            {
+                Some((reflected, reflected_value))
+            } else {
+                let contracted = towards(&worst.inputs, &centroid, 0.5);
+                let contracted_value = self.evaluate_at(params, sink, &contracted)?;
+                match goal.compare(contracted_value, worst.value).is_lt() {
+                    true => Some((contracted, contracted_value)),
+                    false => None,
+                }
+            };
+
+            match candidate {
+                Some((inputs, value)) => *simplex.last_mut().unwrap() = Optimum { inputs, value },
+                // Shrinks every vertex towards the best one.
+                None => {
+                    let best = simplex[0].inputs.clone();
+                    for vertex in simplex.iter_mut().skip(1) {
+                        let inputs = towards(&best, &vertex.inputs, 0.5);
+                        let value = self.evaluate_at(params, sink, &inputs)?;
+                        *vertex = Optimum { inputs, value };
+                    }
+                }
+            }
+        }
+
+        simplex.sort_by(|a, b| goal.compare(a.value, b.value));
+        let best = simplex.swap_remove(0);
+        self.set_inputs(params, &best.inputs)?;
+        Ok(best)
+    }
+
+    fn set_inputs(&mut self, params: &[Param], inputs: &[f32]) -> Result<(), GraphError> {
+        for (param, x) in params.iter().zip(inputs) {
+            self.input(param.node)?.set(vec![*x]);
+        }
+        Ok(())
+    }
+
+    fn evaluate_at(
+        &mut self,
+        params: &[Param],
+        sink: NodeId,
+        inputs: &[f32],
+    ) -> Result<f32, GraphError> {
+        for param in params {
+            self.get(param.node)?;
+        }
+        self.set_inputs(params, inputs)?;
+        let output = self.compute(sink)?;
+        objective(sink, output)
+    }
+}
+
+fn objective(sink: NodeId, output: &[f32]) -> Result<f32, GraphError> {
+    output.first().copied().ok_or_else(|| GraphError::Node {
+        node: sink,
+        message: "objective output is empty".to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Paraboloid with its minimum at (1, -2).
+    fn paraboloid() -> (Graph, Param, Param, NodeId) {
+        let mut graph = Graph::new();
+        let x = graph.node(|input| input).input(vec![0.0]).build();
+        let y = graph.node(|input| input).input(vec![0.0]).build();
+        let sink = graph
+            .node(|input| vec![(input[0] - 1.0).powi(2) + (input[1] + 2.0).powi(2) + 3.0])
+            .child(x)
+            .unwrap()
+            .child(y)
+            .unwrap()
+            .build();
+        (
+            graph,
+            Param::new(x, -5.0, 5.0),
+            Param::new(y, -5.0, 5.0),
+            sink,
+        )
+    }
+
+    fn assert_near(inputs: &[f32], expected: &[f32], tolerance: f32) {
+        for (a, b) in inputs.iter().zip(expected) {
+            assert!((a - b).abs() < tolerance, "{:?} != {:?}", inputs, expected);
+        }
+    }
+
+    #[test]
+    fn test_grid_refine() {
+        let (mut graph, x, y, sink) = paraboloid();
+        let best = graph
+            .grid_refine(&[x, y], sink, Goal::Minimize, 5, 12)
+            .unwrap();
+        assert_near(&best.inputs, &[1.0, -2.0], 1e-3);
+        assert!((best.value - 3.0).abs() < 1e-4);
+        assert_near(graph.input(x.node).unwrap().get().unwrap(), &[1.0], 1e-3);
+    }
+
+    #[test]
+    fn test_random_search() {
+        let (mut graph, x, y, sink) = paraboloid();
+        let top = graph
+            .random_search(&[x, y], sink, Goal::Maximize, 200, 7, 3)
+            .unwrap();
+        assert_eq!(top.len(), 3);
+        assert!(top[0].value >= top[1].value && top[1].value >= top[2].value);
+        // The farthest corner from the minimum is (-5, 5).
+        assert_near(&top[0].inputs, &[-5.0, 5.0], 1.5);
+        assert_eq!(
+            graph.random_search(&[x, y], sink, Goal::Maximize, 200, 7, 3),
+            Ok(top.clone())
+        );
+
+        graph.input(x.node).unwrap().set(vec![0.0]);
+        assert!(graph
+            .random_search(&[x, y], sink, Goal::Maximize, 200, 7, 0)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            graph.input(x.node).unwrap().get(),
+            Some(&top[0].inputs[..1])
+        );
+    }
+
+    #[test]
+    fn test_nelder_mead() {
+        let (mut graph, x, y, sink) = paraboloid();
+        let best = graph
+            .nelder_mead(&[x, y], sink, Goal::Minimize, 100)
+            .unwrap();
+        assert_near(&best.inputs, &[1.0, -2.0], 1e-2);
+
+        let empty = graph.node(|_| vec![]).child(x.node).unwrap().build();
+        assert_eq!(
+            graph.nelder_mead(&[x], empty, Goal::Minimize, 10),
+            Err(GraphError::Node {
+                node: empty,
+                message: "objective output is empty".to_owned()
+            })
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Concurrent evaluation of independent nodes on the rayon thread pool.
+
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
+use crate::error::GraphError;
+use crate::graph::Graph;
+use crate::node::NodeId;
+use crate::sweep::SweepRow;
+use crate::value::Value;
+
+impl<T: Value + Send + Sync> Graph<T> {
+    /// Like [`Graph::compute`], but nodes whose children are all computed run concurrently.
+    /// Nodes are scheduled level by level, a node's level being one more than the highest
+    /// level among the children it waits for.
+    pub fn compute_parallel(&mut self, node: NodeId) -> Result<&[T], GraphError> {
+        self.authorize(node, &[])?;
+        self.advance_evaluations(1);
+        let mut levels: Vec<Vec<NodeId>> = vec![];
+        let mut level_of = HashMap::new();
+        for id in self.evaluation_order(&[node]) {
+            let level = self
+                .inner(id)
+                .down
+                .iter()
+                .filter_map(|child| level_of.get(child))
+                .map(|level| level + 1)
+                .max()
+                .unwrap_or(0);
+            level_of.insert(id, level);
+            if level == levels.len() {
+                levels.push(vec![]);
+            }
+            levels[level].push(id);
+        }
+
+        for level in levels {
+            let inputs = level
+                .iter()
+                .map(|id| self.gather_input(*id, &[node]))
+                .collect::<Result<Vec<_>, _>>()?;
+            let graph = &*self;
+            let outputs: Vec<_> = level
+                .par_iter()
+                .zip(inputs)
+                .map(|(id, input)| graph.run(*id, input))
+                .collect();
+            for (id, output) in level.into_iter().zip(outputs) {
+                self.store_output(id, output?, None);
+            }
+        }
+        self.inner(node)
+            .output()
+            .ok_or(GraphError::NotComputed(node))
+    }
+
+    /// Like [`Graph::sweep`], but the values of the first parameter are swept concurrently, each
+    /// on its own clone of the graph. Clones start from the caches of this graph, which is left
+    /// untouched. Rows are numbered as evaluations like in [`Graph::sweep`], so random streams
+    /// draw the same values.
+    pub fn sweep_parallel(
+        &self,
+        params: &[(NodeId, Vec<Vec<T>>)],
+        sinks: &[NodeId],
+    ) -> Result<Vec<SweepRow<T>>, GraphError> {
+        let Some(((node, values), rest)) = params.split_first() else {
+            return self.clone().sweep(params, sinks);
+        };
+        self.get(*node)?;
+        let rows_per_value: usize = rest.iter().map(|(_, values)| values.len()).product();
+        let chunks = values
+            .par_iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let mut graph = self.clone();
+                graph.advance_evaluations((index * rows_per_value) as u64);
+                graph.input(*node)?.set(value.clone());
+                let mut rows = graph.sweep(rest, sinks)?;
+                for row in rows.iter_mut() {
+                    row.params.insert(0, value.clone());
+                }
+                Ok(rows)
+            })
+            .collect::<Result<Vec<_>, GraphError>>()?;
+        Ok(chunks.into_iter().flatten().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::error::NodeError;
+
+    #[test]
+    fn test_compute_parallel() {
+        let mut graph = Graph::new();
+        let x = graph.node(|input| input).input(vec![1.0]).build();
+        // Each branch waits until two branches have run at once, or gives up after a while.
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let branches: Vec<_> = (0..8)
+            .map(|i| {
+                let (running, peak) = (running.clone(), peak.clone());
+                graph
+                    .node(move |input| {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        let deadline = Instant::now() + Duration::from_secs(5);
+                        while peak.load(Ordering::SeqCst) < 2 && Instant::now() < deadline {
+                            thread::yield_now();
+                        }
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        vec![input[0] * i as f32]
+                    })
+                    .child(x)
+                    .unwrap()
+                    .build()
+            })
+            .collect();
+        let mut total = graph.node(|input| vec![input.iter().sum()]);
+        for branch in branches.iter() {
+            total = total.child(*branch).unwrap();
+        }
+        let total = total.build();
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(8)
+            .build()
+            .unwrap();
+        let output = pool.install(|| graph.compute_parallel(total).unwrap().to_vec());
+        assert_eq!(output, [28.0]);
+        assert!(peak.load(Ordering::SeqCst) > 1);
+        assert!(!graph.is_dirty(branches[3]).unwrap());
+    }
+
+    #[test]
+    fn test_compute_parallel_error() {
+        let mut graph = Graph::new();
+        let x = graph.node(|input| input).input(vec![1.0]).build();
+        let ok = graph.node(|input| input).child(x).unwrap().build();
+        let failing = graph
+            .fallible_node(|_| Err(NodeError::failed("boom")))
+            .child(x)
+            .unwrap()
+            .build();
+        let total = graph
+            .node(|input| input)
+            .child(ok)
+            .unwrap()
+            .child(failing)
+            .unwrap()
+            .build();
+
+        let error = graph.compute_parallel(total).unwrap_err();
+        assert_eq!(error.to_string(), "node #2 failed: boom");
+        assert!(graph.is_dirty(total).unwrap());
+    }
+
+    #[test]
+    fn test_sweep_parallel() {
+        let mut graph = Graph::new();
+        let a = graph.node(|input| input).input(vec![0.0]).build();
+        let b = graph.node(|input| input).input(vec![0.0]).build();
+        let product = graph
+            .node(|input| vec![input[0] * input[1]])
+            .child(a)
+            .unwrap()
+            .child(b)
+            .unwrap()
+            .build();
+
+        let params = [
+            (a, (1..=4).map(|i| vec![i as f32]).collect()),
+            (b, vec![vec![1.0], vec![10.0]]),
+        ];
+        let rows = graph.sweep_parallel(&params, &[product]).unwrap();
+        assert_eq!(rows, graph.clone().sweep(&params, &[product]).unwrap());
+        assert_eq!(rows[5].params, [vec![3.0], vec![10.0]]);
+        assert_eq!(rows[5].outputs, [vec![30.0]]);
+        assert_eq!(graph.input(a).unwrap().get(), Some(&[0.0][..]));
+    }
+
+    #[test]
+    fn test_parallel_rng() {
+        let mut graph = Graph::new();
+        let a = graph.node(|input| input).input(vec![0.0]).build();
+        let b = graph.node(|input| input).input(vec![0.0]).build();
+        let draws: Vec<_> = (0..8)
+            .map(|_| {
+                graph
+                    .contextual_node(|input, context| Ok(vec![input[0] + context.rng().next_f32()]))
+                    .child(a)
+                    .unwrap()
+                    .impure()
+                    .build()
+            })
+            .collect();
+        let mut total = graph.node(|input| input).child(b).unwrap();
+        for draw in draws {
+            total = total.child(draw).unwrap();
+        }
+        let total = total.build();
+        graph.set_seed(42);
+
+        let mut sequential = graph.clone();
+        for _ in 0..3 {
+            let expected = sequential.compute(total).unwrap().to_vec();
+            assert_eq!(graph.compute_parallel(total).unwrap(), expected);
+        }
+
+        let params = [
+            (a, vec![vec![0.0], vec![1.0], vec![2.0]]),
+            (b, vec![vec![0.0], vec![1.0]]),
+        ];
+        assert_eq!(
+            graph.sweep_parallel(&params, &[total]).unwrap(),
+            graph.clone().sweep(&params, &[total]).unwrap()
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! Node functions addressed by string keys.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::NodeError;
+use crate::node::NodeFn;
+use crate::value::Value;
+
+/// Node functions keyed by name. Closures cannot be stored, so graphs built from registered ops
+/// refer to them by key and are resolved against a registry when they are loaded.
+pub struct OpRegistry<T = f32> {
+    ops: HashMap<String, NodeFn<T>>,
+    descriptions: HashMap<String, String>,
+    // Deprecated keys with the key of their replacement, if any.
+    deprecations: HashMap<String, Option<String>>,
+}
+
+impl<T: Value> OpRegistry<T> {
+    pub fn new() -> Self {
+        Self {
+            ops: HashMap::new(),
+            descriptions: HashMap::new(),
+            deprecations: HashMap::new(),
+        }
+    }
+
+    /// Registers `func` under `key`, replacing any op registered under it before.
+    pub fn register<F>(&mut self, key: impl Into<String>, func: F)
+    where
+        F: Fn(Vec<T>) -> Result<Vec<T>, NodeError> + Send + Sync + 'static,
+    {
+        self.ops
+            .insert(key.into(), Arc::new(move |input, _| func(input)));
+    }
+
+    /// Describes the op registered under `key`, nodes created from it start with this
+    /// description.
+    pub fn describe(&mut self, key: impl Into<String>, description: impl Into<String>) {
+        self.descriptions.insert(key.into(), description.into());
+    }
+
+    pub fn description(&self, key: &str) -> Option<&str> {
+        self.descriptions.get(key).map(String::as_str)
+    }
+
+    /// Marks the op registered under `key` as deprecated in favor of `replacement`. Loading a
+    /// graph that uses it still works but reports a warning.
+    pub fn deprecate(&mut self, key: impl Into<String>, replacement: Option<&str>) {
+        self.deprecations
+            .insert(key.into(), replacement.map(str::to_owned));
+    }
+
+    /// `Some` with the replacement, if one was given, when the op is deprecated.
+    pub fn deprecation(&self, key: &str) -> Option<Option<&str>> {
+        self.deprecations.get(key).map(Option::as_deref)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.ops.contains_key(key)
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&NodeFn<T>> {
+        self.ops.get(key)
+    }
+}
+
+impl<T: Value> Default for OpRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,347 @@
+//! Counter-based random number streams.
+//!
+//! Every draw is a pure function of `(master seed, stream, evaluation, draw index)` computed with
+//! Philox4x32-10, so the values a random node sees do not depend on the order in which nodes are
+//! evaluated. Node functions get the stream of their node for the current evaluation from
+//! [`EvalContext::rng`], seeded with [`Graph::set_seed`], so Monte Carlo runs are reproducible
+//! and independent across nodes.
+//!
+//! [`EvalContext::rng`]: crate::EvalContext::rng
+//! [`Graph::set_seed`]: crate::Graph::set_seed
+//!
+//! [`Sampler`] layers variance reduction on top: antithetic pairs and randomized Halton or Sobol
+//! quasi-random sequences, where the evaluation selects the point and the draw index selects the
+//! dimension.
+
+const MULTIPLIER_0: u32 = 0xD251_1F53;
+const MULTIPLIER_1: u32 = 0xCD9E_8D57;
+const WEYL_0: u32 = 0x9E37_79B9;
+const WEYL_1: u32 = 0xBB67_AE85;
+const ROUNDS: usize = 10;
+
+/// Philox4x32-10 block function: maps a 128-bit counter and a 64-bit key to 128 random bits.
+pub fn philox4x32(counter: [u32; 4], key: [u32; 2]) -> [u32; 4] {
+    let mut counter = counter;
+    let mut key = key;
+    for round in 0..ROUNDS {
+        if round > 0 {
+            key[0] = key[0].wrapping_add(WEYL_0);
+            key[1] = key[1].wrapping_add(WEYL_1);
+        }
+        let product_0 = u64::from(MULTIPLIER_0) * u64::from(counter[0]);
+        let product_1 = u64::from(MULTIPLIER_1) * u64::from(counter[2]);
+        counter = [
+            (product_1 >> 32) as u32 ^ counter[1] ^ key[0],
+            product_1 as u32,
+            (product_0 >> 32) as u32 ^ counter[3] ^ key[1],
+            product_0 as u32,
+        ];
+    }
+    counter
+}
+
+/// Random stream of one node during one evaluation.
+///
+/// A stream yields at most 2^34 values per evaluation and evaluations are distinguished modulo
+/// 2^32.
+#[derive(Clone, Debug)]
+pub struct RngStream {
+    key: [u32; 2],
+    stream: u64,
+    evaluation: u32,
+    block: u32,
+    buffer: [u32; 4],
+    used: usize,
+}
+
+impl RngStream {
+    pub fn new(master_seed: u64, stream: u64, evaluation: u64) -> Self {
+        Self {
+            key: [master_seed as u32, (master_seed >> 32) as u32],
+            stream,
+            evaluation: evaluation as u32,
+            block: 0,
+            buffer: [0; 4],
+            used: 4,
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        if self.used == 4 {
+            let counter = [
+                self.block,
+                self.evaluation,
+                self.stream as u32,
+                (self.stream >> 32) as u32,
+            ];
+            self.buffer = philox4x32(counter, self.key);
+            self.block = self.block.wrapping_add(1);
+            self.used = 0;
+        }
+        let value = self.buffer[self.used];
+        self.used += 1;
+        value
+    }
+
+    /// Uniform value in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        // The top 24 bits fill the f32 mantissa exactly.
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
+
+impl Iterator for RngStream {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        Some(self.next_f32())
+    }
+}
+
+/// How a [`Sampler`] turns evaluations into uniform values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SamplingMode {
+    /// Independent Philox draws.
+    #[default]
+    Pseudo,
+    /// Odd evaluations mirror the draws `u` of the preceding even one as `1 - u`.
+    Antithetic,
+    /// Halton sequence with one prime base per dimension.
+    Halton,
+    /// Sobol sequence over the first [`SOBOL_DIMENSIONS`] draws of an evaluation, later draws are
+    /// independent Philox draws.
+    Sobol,
+}
+
+/// Number of dimensions supported by [`SamplingMode::Sobol`].
+pub const SOBOL_DIMENSIONS: usize = SOBOL_POLYNOMIALS.len() + 1;
+
+// Degree, coefficients and initial direction numbers of the primitive polynomials for
+// dimensions 2 and up, from Joe and Kuo's new-joe-kuo-6.21201 table.
+const SOBOL_POLYNOMIALS: [(u32, u32, &[u32]); 9] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+];
+
+const SOBOL_BITS: usize = 32;
+
+fn sobol_directions(dimension: usize) -> [u32; SOBOL_BITS] {
+    let mut directions = [0u32; SOBOL_BITS];
+    if dimension == 0 {
+        for (bit, direction) in directions.iter_mut().enumerate() {
+            *direction = 1 << (31 - bit);
+        }
+        return directions;
+    }
+
+    let (degree, coefficients, initial) = SOBOL_POLYNOMIALS[dimension - 1];
+    let degree = degree as usize;
+    let mut m = [0u32; SOBOL_BITS];
+    m[..degree].copy_from_slice(initial);
+    for k in degree..SOBOL_BITS {
+        let mut value = m[k - degree] ^ (m[k - degree] << degree);
+        for j in 1..degree {
+            if (coefficients >> (degree - 1 - j)) & 1 == 1 {
+                value ^= m[k - j] << j;
+            }
+        }
+        m[k] = value;
+    }
+    for (bit, direction) in directions.iter_mut().enumerate() {
+        *direction = m[bit] << (31 - bit);
+    }
+    directions
+}
+
+fn sobol(index: u64, dimension: usize) -> f32 {
+    let directions = sobol_directions(dimension);
+    let value = directions
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| (index >> bit) & 1 == 1)
+        .fold(0u32, |acc, (_, direction)| acc ^ direction);
+    (value >> 8) as f32 / (1u32 << 24) as f32
+}
+
+fn nth_prime(n: usize) -> u64 {
+    (2u64..)
+        .filter(|candidate| {
+            (2..)
+                .take_while(|d| d * d <= *candidate)
+                .all(|d| candidate % d != 0)
+        })
+        .nth(n)
+        .unwrap_or(2)
+}
+
+fn halton(index: u64, base: u64) -> f32 {
+    let mut index = index;
+    let mut fraction = 1.0f64;
+    let mut result = 0.0f64;
+    while index > 0 {
+        fraction /= base as f64;
+        result += fraction * (index % base) as f64;
+        index /= base;
+    }
+    result as f32
+}
+
+/// Uniform values of one node during one evaluation under a [`SamplingMode`].
+///
+/// Quasi-random modes are randomized with a per-stream shift modulo 1, so different nodes do not
+/// see identical sequences while each keeps its low discrepancy across evaluations.
+#[derive(Clone, Debug)]
+pub struct Sampler {
+    mode: SamplingMode,
+    evaluation: u64,
+    dimension: usize,
+    rng: RngStream,
+    shifts: RngStream,
+}
+
+impl Sampler {
+    pub fn new(mode: SamplingMode, master_seed: u64, stream: u64, evaluation: u64) -> Self {
+        let rng = match mode {
+            SamplingMode::Antithetic => RngStream::new(master_seed, stream, evaluation / 2),
+            _ => RngStream::new(master_seed, stream, evaluation),
+        };
+        Self {
+            mode,
+            evaluation,
+            dimension: 0,
+            rng,
+            shifts: RngStream::new(master_seed, stream, 0),
+        }
+    }
+
+    /// Uniform value in `[0, 1]`.
+    pub fn next_f32(&mut self) -> f32 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        match self.mode {
+            SamplingMode::Pseudo => self.rng.next_f32(),
+            SamplingMode::Antithetic => {
+                let value = self.rng.next_f32();
+                if self.evaluation % 2 == 1 {
+                    1.0 - value
+                } else {
+                    value
+                }
+            }
+            SamplingMode::Halton => {
+                let value = halton(self.evaluation + 1, nth_prime(dimension));
+                (value + self.shifts.next_f32()).fract()
+            }
+            SamplingMode::Sobol if dimension >= SOBOL_DIMENSIONS => self.rng.next_f32(),
+            SamplingMode::Sobol => {
+                let value = sobol(self.evaluation + 1, dimension);
+                (value + self.shifts.next_f32()).fract()
+            }
+        }
+    }
+}
+
+impl Iterator for Sampler {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        Some(self.next_f32())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_philox_known_answers() {
+        assert_eq!(
+            philox4x32([0; 4], [0; 2]),
+            [0x6627e8d5, 0xe169c58d, 0xbc57ac4c, 0x9b00dbd8]
+        );
+        assert_eq!(
+            philox4x32([u32::MAX; 4], [u32::MAX; 2]),
+            [0x408f276d, 0x41c83b0e, 0xa20bc7c6, 0x6d5451fd]
+        );
+        assert_eq!(
+            philox4x32(
+                [0x243f6a88, 0x85a308d3, 0x13198a2e, 0x03707344],
+                [0xa4093822, 0x299f31d0]
+            ),
+            [0xd16cfe09, 0x94fdcceb, 0x5001e420, 0x24126ea1]
+        );
+    }
+
+    #[test]
+    fn test_streams_are_reproducible_and_independent() {
+        let first: Vec<f32> = RngStream::new(42, 3, 7).take(10).collect();
+        let second: Vec<f32> = RngStream::new(42, 3, 7).take(10).collect();
+        assert_eq!(first, second);
+        assert!(first.iter().all(|x| (0.0..1.0).contains(x)));
+
+        assert_ne!(first, RngStream::new(42, 4, 7).take(10).collect::<Vec<_>>());
+        assert_ne!(first, RngStream::new(42, 3, 8).take(10).collect::<Vec<_>>());
+        assert_ne!(first, RngStream::new(43, 3, 7).take(10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_antithetic_pairs() {
+        let even: Vec<f32> = Sampler::new(SamplingMode::Antithetic, 1, 2, 4)
+            .take(5)
+            .collect();
+        let odd: Vec<f32> = Sampler::new(SamplingMode::Antithetic, 1, 2, 5)
+            .take(5)
+            .collect();
+        even.iter()
+            .zip(&odd)
+            .for_each(|(u, v)| assert_eq!(u + v, 1.0));
+    }
+
+    #[test]
+    fn test_low_discrepancy_sequences() {
+        assert_eq!(
+            (1..5).map(|index| halton(index, 3)).collect::<Vec<_>>(),
+            [1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0, 4.0 / 9.0]
+        );
+
+        let points: Vec<(f32, f32)> = (1..5)
+            .map(|index| (sobol(index, 0), sobol(index, 1)))
+            .collect();
+        assert_eq!(
+            points,
+            [(0.5, 0.5), (0.25, 0.75), (0.75, 0.25), (0.125, 0.625)]
+        );
+        (0..SOBOL_DIMENSIONS).for_each(|dimension| assert_eq!(sobol(1, dimension), 0.5));
+    }
+
+    #[test]
+    fn test_quasi_random_sampler_stratifies() {
+        let mut bins: Vec<usize> = (0..63)
+            .map(|evaluation| Sampler::new(SamplingMode::Sobol, 9, 0, evaluation).next_f32())
+            .map(|value| (value * 64.0) as usize)
+            .collect();
+        bins.sort();
+        bins.dedup();
+        // Points k/64 shifted by the same offset land in pairwise distinct bins of width 1/64.
+        assert_eq!(bins.len(), 63);
+
+        let other = Sampler::new(SamplingMode::Sobol, 9, 1, 0).next_f32();
+        assert_ne!(Sampler::new(SamplingMode::Sobol, 9, 0, 0).next_f32(), other);
+
+        // Draws past the last dimension come from the pseudo-random stream.
+        let draws: Vec<f32> = Sampler::new(SamplingMode::Sobol, 9, 0, 5)
+            .skip(SOBOL_DIMENSIONS)
+            .take(2)
+            .collect();
+        let pseudo: Vec<f32> = RngStream::new(9, 0, 5).take(2).collect();
+        assert_eq!(draws, pseudo);
+    }
+}
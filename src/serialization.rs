@@ -0,0 +1,372 @@
+//! Storable description of a graph, for use with any serde format.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::GraphError;
+use crate::graph::Graph;
+use crate::node::{Bypass, NodeId};
+use crate::registry::OpRegistry;
+use crate::rng::SamplingMode;
+use crate::value::Value;
+
+/// Schema version written by [`Graph::to_def`].
+pub const VERSION: u32 = 1;
+
+/// Topology, settings and input values of a graph. Nodes refer to their children by position in
+/// `nodes` and to their function by registry key.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GraphDef<T = f32> {
+    /// Schema version, 0 for definitions stored before versioning was introduced.
+    #[serde(default)]
+    pub version: u32,
+    pub nodes: Vec<NodeDef<T>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NodeDef<T = f32> {
+    pub op: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub children: Vec<usize>,
+    pub arity: Option<usize>,
+    pub input: Option<Vec<T>>,
+    #[serde(default)]
+    pub in_place: bool,
+    #[serde(default)]
+    pub sensitive: bool,
+    #[serde(default = "default_pure")]
+    pub pure: bool,
+    #[serde(default)]
+    pub sampling: SamplingMode,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Whether the function of the node is bypassed, see [`Graph::disable`].
+    #[serde(default)]
+    pub disabled: bool,
+    /// Output of a disabled node, which passes its input through when there is none, see
+    /// [`Graph::disable_with`].
+    pub fallback: Option<Vec<T>>,
+    /// Up-to-date output at the time the definition was taken, if requested.
+    pub cache: Option<Vec<T>>,
+}
+
+fn default_pure() -> bool {
+    true
+}
+
+/// Problem found while loading a definition that does not prevent it from being loaded, see
+/// [`Graph::from_def_with_warnings`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadWarning {
+    /// The definition was stored with an older schema version, storing it again upgrades it.
+    OldVersion(u32),
+    /// `node` uses an op the registry marks as deprecated.
+    DeprecatedOp {
+        node: NodeId,
+        op: String,
+        replacement: Option<String>,
+    },
+}
+
+impl fmt::Display for LoadWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadWarning::OldVersion(version) => write!(
+                f,
+                "definition version {} is older than {}",
+                version, VERSION
+            ),
+            LoadWarning::DeprecatedOp {
+                node,
+                op,
+                replacement,
+            } => {
+                write!(f, "node {} uses deprecated op {:?}", node, op)?;
+                match replacement {
+                    Some(replacement) => write!(f, ", use {:?} instead", replacement),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl<T: Value> Graph<T> {
+    /// Describes the graph for storage, with the outputs of up-to-date nodes when `caches` is
+    /// set. Outputs of nodes restricted to a capability are left out. Fails if a node was not
+    /// created from a registered op.
+    pub fn to_def(&self, caches: bool) -> Result<GraphDef<T>, GraphError> {
+        // Removed nodes leave gaps in the ids, positions in the definition are contiguous.
+        let positions: HashMap<NodeId, usize> = self
+            .nodes()
+            .enumerate()
+            .map(|(position, node)| (node, position))
+            .collect();
+        let nodes = self
+            .nodes()
+            .map(|node| {
+                let inner = self.inner(node);
+                let op = inner.op.clone().ok_or(GraphError::Unregistered(node))?;
+                let cache = match caches && !inner.is_dirty() && inner.capabilities.is_empty() {
+                    true => inner.output().map(<[T]>::to_vec),
+                    false => None,
+                };
+                Ok(NodeDef {
+                    op,
+                    name: inner.name.clone(),
+                    description: inner.description.clone(),
+                    children: inner.down.iter().map(|child| positions[child]).collect(),
+                    arity: inner.arity,
+                    input: inner.input.clone(),
+                    in_place: inner.in_place,
+                    sensitive: inner.sensitive,
+                    pure: inner.pure,
+                    sampling: inner.sampling,
+                    capabilities: inner.capabilities.clone(),
+                    disabled: inner.disabled.is_some(),
+                    fallback: match &inner.disabled {
+                        Some(Bypass::Fallback(fallback)) => Some(fallback.clone()),
+                        _ => None,
+                    },
+                    cache,
+                })
+            })
+            .collect::<Result<_, GraphError>>()?;
+        Ok(GraphDef {
+            version: VERSION,
+            nodes,
+        })
+    }
+
+    /// Rebuilds a graph, resolving op keys against `registry`. The definition is validated like
+    /// a graph built by hand, unknown children and cycles are rejected. Warnings are dropped,
+    /// see [`Graph::from_def_with_warnings`] to collect them.
+    pub fn from_def(def: GraphDef<T>, registry: &OpRegistry<T>) -> Result<Self, GraphError> {
+        Self::from_def_with_warnings(def, registry).map(|(graph, _)| graph)
+    }
+
+    /// Like [`Graph::from_def`], also returning what should be upgraded in the definition:
+    /// an older schema version and deprecated ops. Definitions of a newer version are rejected.
+    pub fn from_def_with_warnings(
+        def: GraphDef<T>,
+        registry: &OpRegistry<T>,
+    ) -> Result<(Self, Vec<LoadWarning>), GraphError> {
+        let mut warnings = vec![];
+        match def.version {
+            version if version > VERSION => return Err(GraphError::UnsupportedVersion(version)),
+            version if version < VERSION => warnings.push(LoadWarning::OldVersion(version)),
+            _ => {}
+        }
+        let mut graph = Graph::default();
+        for node in def.nodes.iter() {
+            let id = graph.registered_node(registry, &node.op)?.build();
+            if let Some(replacement) = registry.deprecation(&node.op) {
+                warnings.push(LoadWarning::DeprecatedOp {
+                    node: id,
+                    op: node.op.clone(),
+                    replacement: replacement.map(str::to_owned),
+                });
+            }
+        }
+        let mut caches = vec![];
+        for (index, node) in def.nodes.into_iter().enumerate() {
+            let id = NodeId(index);
+            if let Some(arity) = node.arity {
+                graph.set_arity(id, arity)?;
+            }
+            for child in node.children {
+                graph.add_children(id, NodeId(child))?;
+            }
+            if let Some(name) = node.name {
+                graph.set_name(id, name)?;
+            }
+            if let Some(description) = node.description {
+                graph.set_description(id, description)?;
+            }
+            if let Some(input) = node.input {
+                graph.input(id)?.set(input);
+            }
+            graph.set_in_place(id, node.in_place)?;
+            graph.set_sensitive(id, node.sensitive)?;
+            graph.set_pure(id, node.pure)?;
+            graph.set_sampling(id, node.sampling)?;
+            for capability in node.capabilities {
+                graph.require_capability(id, capability)?;
+            }
+            match (node.disabled, node.fallback) {
+                (true, Some(fallback)) => graph.disable_with(id, fallback)?,
+                (true, None) => graph.disable(id)?,
+                (false, _) => {}
+            }
+            caches.push(node.cache);
+        }
+        graph.restore_caches(caches);
+        Ok((graph, warnings))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::NodeError;
+
+    fn registry() -> OpRegistry {
+        let mut registry = OpRegistry::new();
+        registry.register("sum", |input: Vec<f32>| Ok(vec![input.iter().sum()]));
+        registry.register("sqrt", |input: Vec<f32>| match input.first() {
+            Some(x) => Ok(vec![x.sqrt()]),
+            None => Err(NodeError::MissingInput(0)),
+        });
+        registry.describe("sum", "sum of the inputs");
+        registry
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let registry = registry();
+        let mut graph = Graph::new();
+        let a = graph
+            .registered_node(&registry, "sum")
+            .unwrap()
+            .input(vec![1.0, 3.0])
+            .build();
+        let b = graph
+            .registered_node(&registry, "sum")
+            .unwrap()
+            .input(vec![5.0])
+            .build();
+        let root = graph
+            .registered_node(&registry, "sqrt")
+            .unwrap()
+            .name("root")
+            .unwrap()
+            .description("square root of a")
+            .arity(1)
+            .child(a)
+            .unwrap()
+            .build();
+        graph.compute(root).unwrap();
+        graph.compute(b).unwrap();
+        graph.input(b).unwrap().set(vec![6.0]);
+        graph.disable_with(b, vec![7.0]).unwrap();
+
+        let json = serde_json::to_string(&graph.to_def(true).unwrap()).unwrap();
+        let def: GraphDef = serde_json::from_str(&json).unwrap();
+        assert_eq!(def.nodes[root.0].cache, Some(vec![2.0]));
+        assert_eq!(def.nodes[b.0].cache, None);
+
+        let mut loaded = Graph::from_def(def, &registry).unwrap();
+        assert_eq!(loaded.node_by_name("root"), Some(root));
+        assert_eq!(loaded.description(root), Some("square root of a"));
+        assert_eq!(loaded.description(a), Some("sum of the inputs"));
+        assert_eq!(loaded.node_info(root).unwrap().arity, Some(1));
+        assert_eq!(
+            loaded.add_children(root, b),
+            Err(GraphError::ArityExceeded {
+                node: root,
+                arity: 1
+            })
+        );
+        assert!(!loaded.is_dirty(root).unwrap());
+        assert!(loaded.is_dirty(b).unwrap());
+        assert_eq!(loaded.compute(b).unwrap(), [7.0]);
+        loaded.enable(b).unwrap();
+        assert_eq!(loaded.compute(b).unwrap(), [6.0]);
+
+        loaded.input(a).unwrap().set(vec![9.0]);
+        assert!(loaded.is_dirty(root).unwrap());
+        assert_eq!(loaded.compute(root).unwrap(), [3.0]);
+    }
+
+    #[test]
+    fn test_restricted_caches() {
+        let registry = registry();
+        let mut graph = Graph::new();
+        let secret = graph
+            .registered_node(&registry, "sum")
+            .unwrap()
+            .input(vec![4.0])
+            .require_capability("risk")
+            .build();
+        let root = graph
+            .registered_node(&registry, "sqrt")
+            .unwrap()
+            .child(secret)
+            .unwrap()
+            .build();
+        graph.compute(root).unwrap();
+
+        let def = graph.to_def(true).unwrap();
+        assert_eq!(def.nodes[secret.0].cache, None);
+        assert_eq!(def.nodes[root.0].cache, Some(vec![2.0]));
+        assert_eq!(def.nodes[secret.0].capabilities, ["risk"]);
+    }
+
+    #[test]
+    fn test_invalid_def() {
+        let registry = registry();
+        let mut graph = Graph::new();
+        graph.add_node(|input| input);
+        assert_eq!(
+            graph.to_def(false),
+            Err(GraphError::Unregistered(NodeId(0)))
+        );
+
+        let def: GraphDef = serde_json::from_str(r#"{"nodes": [{"op": "max"}]}"#).unwrap();
+        assert_eq!(def.version, 0);
+        assert_eq!(
+            Graph::from_def(def, &registry).err(),
+            Some(GraphError::UnknownOp("max".to_owned()))
+        );
+
+        let def: GraphDef = serde_json::from_str(
+            r#"{"nodes": [{"op": "sum", "children": [1]}, {"op": "sum", "children": [0]}]}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            Graph::from_def(def, &registry),
+            Err(GraphError::CycleDetected(_))
+        ));
+    }
+
+    #[test]
+    fn test_warnings() {
+        let mut registry = registry();
+        registry.register("total", |input: Vec<f32>| Ok(vec![input.iter().sum()]));
+        registry.deprecate("sum", Some("total"));
+        registry.deprecate("sqrt", None);
+
+        let def: GraphDef = serde_json::from_str(
+            r#"{"nodes": [{"op": "sum", "input": [4.0]}, {"op": "sqrt", "children": [0]}]}"#,
+        )
+        .unwrap();
+        let (mut graph, warnings) = Graph::from_def_with_warnings(def, &registry).unwrap();
+        assert_eq!(graph.compute(NodeId(1)).unwrap(), [2.0]);
+        let warnings: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            warnings,
+            [
+                "definition version 0 is older than 1",
+                "node #0 uses deprecated op \"sum\", use \"total\" instead",
+                "node #1 uses deprecated op \"sqrt\"",
+            ]
+        );
+
+        let mut def = graph.to_def(false).unwrap();
+        assert_eq!(def.version, VERSION);
+        def.nodes[0].op = "total".to_owned();
+        def.nodes.truncate(1);
+        let (_, warnings) = Graph::from_def_with_warnings(def.clone(), &registry).unwrap();
+        assert!(warnings.is_empty());
+
+        def.version = VERSION + 1;
+        assert_eq!(
+            Graph::from_def(def, &registry).err(),
+            Some(GraphError::UnsupportedVersion(VERSION + 1))
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Shadow evaluation of a candidate replacement for a node, see [`Graph::set_shadow`].
+//!
+//! [`Graph::set_shadow`]: crate::Graph::set_shadow
+
+use std::sync::Arc;
+
+use crate::error::GraphError;
+use crate::node::NodeId;
+
+pub(crate) type DistanceFn<T> = Arc<dyn Fn(&[T], &[T]) -> f64 + Send + Sync>;
+
+// Candidate evaluated alongside the node it may replace.
+#[derive(Clone)]
+pub(crate) struct Shadow<T> {
+    pub(crate) candidate: NodeId,
+    pub(crate) distance: DistanceFn<T>,
+    pub(crate) report: ShadowReport,
+}
+
+/// How the outputs of a candidate compared with those of the node it shadows so far.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShadowReport {
+    /// Evaluations of the shadowed node the candidate was evaluated alongside.
+    pub evaluations: usize,
+    /// Evaluations in which the candidate failed, these have no distance.
+    pub failures: usize,
+    /// Evaluations in which the outputs were at a distance above zero.
+    pub divergences: usize,
+    pub max_distance: f64,
+    pub total_distance: f64,
+    pub last_error: Option<GraphError>,
+}
+
+impl ShadowReport {
+    /// Mean distance over the evaluations in which the candidate succeeded.
+    pub fn mean_distance(&self) -> f64 {
+        match self.evaluations - self.failures {
+            0 => 0.0,
+            compared => self.total_distance / compared as f64,
+        }
+    }
+
+    pub(crate) fn record(&mut self, distance: Result<f64, GraphError>) {
+        self.evaluations += 1;
+        match distance {
+            Ok(distance) => {
+                if distance > 0.0 {
+                    self.divergences += 1;
+                }
+                self.max_distance = self.max_distance.max(distance);
+                self.total_distance += distance;
+            }
+            Err(error) => {
+                self.failures += 1;
+                self.last_error = Some(error);
+            }
+        }
+    }
+}
+
+/// Largest absolute difference between values at the same position, infinite when the lengths
+/// differ.
+pub fn max_abs_diff<T: Clone + Into<f64>>(a: &[T], b: &[T]) -> f64 {
+    if a.len() != b.len() {
+        return f64::INFINITY;
+    }
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a.clone().into() - b.clone().into()).abs())
+        .fold(0.0, f64::max)
+}
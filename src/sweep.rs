@@ -0,0 +1,141 @@
+//! Evaluation of sinks over every combination of values of selected input nodes.
+
+use crate::error::GraphError;
+use crate::graph::Graph;
+use crate::node::NodeId;
+use crate::value::Value;
+
+/// Outputs of the swept sinks for one combination of inputs, see [`Graph::sweep`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepRow<T = f32> {
+    /// Input of each swept node, in the order the parameters were given.
+    pub params: Vec<Vec<T>>,
+    /// Output of each sink, in the order the sinks were given.
+    pub outputs: Vec<Vec<T>>,
+}
+
+impl<T: Value> Graph<T> {
+    /// Computes `sinks` for every combination of the inputs listed for each node of `params`,
+    /// returning one row per combination. The last parameter varies fastest and only the inputs
+    /// that differ from the previous row are set, so nodes that do not depend on them keep their
+    /// cached output. The swept nodes are left with the inputs of the last row.
+    pub fn sweep(
+        &mut self,
+        params: &[(NodeId, Vec<Vec<T>>)],
+        sinks: &[NodeId],
+    ) -> Result<Vec<SweepRow<T>>, GraphError> {
+        for (node, _) in params {
+            self.get(*node)?;
+        }
+        for sink in sinks {
+            self.authorize(*sink, &[])?;
+        }
+        if params.iter().any(|(_, values)| values.is_empty()) {
+            return Ok(vec![]);
+        }
+
+        let mut rows = vec![];
+        let mut indices = vec![0; params.len()];
+        let mut current: Vec<Option<usize>> = vec![None; params.len()];
+        loop {
+            for (i, (node, values)) in params.iter().enumerate() {
+                if current[i] != Some(indices[i]) {
+                    self.input(*node)?.set(values[indices[i]].clone());
+                    current[i] = Some(indices[i]);
+                }
+            }
+            self.compute_nodes(sinks)?;
+            let outputs = sinks
+                .iter()
+                .map(|sink| {
+                    self.inner(*sink)
+                        .output()
+                        .map(<[T]>::to_vec)
+                        .ok_or(GraphError::NotComputed(*sink))
+                })
+                .collect::<Result<_, _>>()?;
+            rows.push(SweepRow {
+                params: params
+                    .iter()
+                    .zip(indices.iter())
+                    .map(|((_, values), index)| values[*index].clone())
+                    .collect(),
+                outputs,
+            });
+
+            // Advances to the next combination, like an odometer.
+            let mut i = params.len();
+            loop {
+                if i == 0 {
+                    return Ok(rows);
+                }
+                i -= 1;
+                indices[i] += 1;
+                if indices[i] < params[i].1.len() {
+                    break;
+                }
+                indices[i] = 0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn test_sweep() {
+        let mut graph = Graph::new();
+        let a = graph.node(|input| input).input(vec![0.0]).build();
+        let b = graph.node(|input| input).input(vec![0.0]).build();
+        let evaluations = Arc::new(AtomicUsize::new(0));
+        let counter = evaluations.clone();
+        let square = graph
+            .node(move |input| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                vec![input[0] * input[0]]
+            })
+            .child(a)
+            .unwrap()
+            .build();
+        let total = graph
+            .node(|input| vec![input.iter().sum()])
+            .child(square)
+            .unwrap()
+            .child(b)
+            .unwrap()
+            .build();
+
+        let params = [
+            (a, vec![vec![1.0], vec![2.0]]),
+            (b, vec![vec![10.0], vec![20.0], vec![30.0]]),
+        ];
+        let rows = graph.sweep(&params, &[total, square]).unwrap();
+        let outputs: Vec<_> = rows.iter().map(|row| row.outputs.clone()).collect();
+        assert_eq!(
+            outputs,
+            [
+                [vec![11.0], vec![1.0]],
+                [vec![21.0], vec![1.0]],
+                [vec![31.0], vec![1.0]],
+                [vec![14.0], vec![4.0]],
+                [vec![24.0], vec![4.0]],
+                [vec![34.0], vec![4.0]],
+            ]
+        );
+        assert_eq!(rows[4].params, [vec![2.0], vec![20.0]]);
+        // `square` only depends on the slower parameter.
+        assert_eq!(evaluations.load(Ordering::SeqCst), 2);
+        assert_eq!(graph.input(b).unwrap().get(), Some(&[30.0][..]));
+
+        assert!(graph.sweep(&[(a, vec![])], &[total]).unwrap().is_empty());
+        assert_eq!(
+            graph.sweep(&[(NodeId(9), vec![vec![1.0]])], &[total]),
+            Err(GraphError::UnknownNode(NodeId(9)))
+        );
+    }
+}
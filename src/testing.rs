@@ -0,0 +1,179 @@
+//! Assertion helpers for tests of graphs built with this crate.
+
+use crate::value::Value;
+use crate::{Graph, GraphError, NodeId};
+
+/// Asserts that every element of the node output is within `tol` of `expected`. On failure the
+/// message lists the offending element together with the values the node was computed from.
+#[macro_export]
+macro_rules! assert_output_eq {
+    ($graph:expr, $node:expr, $expected:expr, $tol:expr) => {{
+        let graph: &mut $crate::Graph = &mut $graph;
+        let node: $crate::NodeId = $node;
+        let expected: &[f32] = &$expected;
+        let actual = match graph.compute(node) {
+            Ok(output) => output.to_vec(),
+            Err(error) => panic!("computing {} failed: {}", node, error),
+        };
+        if let Some(message) =
+            $crate::testing::output_mismatch(graph, node, &actual, expected, $tol)
+        {
+            panic!("{}", message);
+        }
+    }};
+}
+
+#[doc(hidden)]
+pub fn output_mismatch(
+    graph: &Graph,
+    node: NodeId,
+    actual: &[f32],
+    expected: &[f32],
+    tol: f32,
+) -> Option<String> {
+    let failure = if actual.len() != expected.len() {
+        format!(
+            "output length {} does not match expected length {}",
+            actual.len(),
+            expected.len()
+        )
+    } else {
+        let index = actual
+            .iter()
+            .zip(expected)
+            .position(|(a, e)| (a - e).abs() > tol || a.is_nan() != e.is_nan())?;
+        format!(
+            "output[{}] = {} differs from expected {} by more than {}",
+            index, actual[index], expected[index], tol
+        )
+    };
+
+    let inner = graph.inner(node);
+    let mut message = format!(
+        "{}\n  actual:   {}\n  expected: {:?}",
+        failure,
+        inner.display_value(actual),
+        expected
+    );
+    for child in inner.down.iter() {
+        let child_inner = graph.inner(*child);
+        let value = match child_inner.cache {
+            None => "<not computed>".to_owned(),
+            Some(ref cache) => child_inner.display_value(cache),
+        };
+        message.push_str(&format!("\n  child {}: {}", child, value));
+    }
+    if let Some(ref input) = inner.input {
+        message.push_str(&format!("\n  input:    {}", inner.display_value(input)));
+    }
+    Some(message)
+}
+
+/// Asserts that [`Graph::validate`] finds no wiring problem. On failure the message lists every
+/// problem with the path from an input up to the offending node.
+#[macro_export]
+macro_rules! assert_graph_valid {
+    ($graph:expr) => {{
+        if let Some(message) = $crate::testing::validation_failure(&$graph) {
+            panic!("{}", message);
+        }
+    }};
+}
+
+#[doc(hidden)]
+pub fn validation_failure<T: Value>(graph: &Graph<T>) -> Option<String> {
+    let errors = graph.validate().err()?;
+    let mut message = format!("graph has {} wiring problems", errors.len());
+    for error in errors {
+        message.push_str(&format!("\n  {}", error));
+        let node = match error {
+            GraphError::UnderConnected { node, .. } | GraphError::MissingPort { node, .. } => node,
+            _ => continue,
+        };
+        // Follows the first child of each node down to one without children.
+        let mut path = vec![node];
+        while let Some(child) = path.last().and_then(|last| graph.inner(*last).down.first()) {
+            path.push(*child);
+        }
+        let path: Vec<String> = path.iter().rev().map(NodeId::to_string).collect();
+        message.push_str(&format!("\n    path: {}", path.join(" -> ")));
+    }
+    Some(message)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Graph;
+
+    #[test]
+    fn test_assert_output_eq() {
+        let mut graph = Graph::new();
+        let node_1 = graph.add_node(|input| vec![input.first().unwrap().sin()]);
+        let node_2 = graph.add_node(|input| vec![input.first().unwrap() * input.get(1).unwrap()]);
+
+        graph.input(node_1).unwrap().set(vec![1.0]);
+        graph.input(node_2).unwrap().set(vec![2.0]);
+        graph.add_children(node_2, node_1).unwrap();
+
+        assert_output_eq!(graph, node_2, [1.68294], 1e-5);
+    }
+
+    #[test]
+    #[should_panic(expected = "differs from expected 1.7 by more than 0.001\n  \
+                               actual:   [1.6829419]\n  \
+                               expected: [1.7]\n  \
+                               child #0: [0.84147096]\n  \
+                               input:    [2.0]")]
+    fn test_assert_output_eq_diagnostics() {
+        let mut graph = Graph::new();
+        let node_1 = graph.add_node(|input| vec![input.first().unwrap().sin()]);
+        let node_2 = graph.add_node(|input| vec![input.first().unwrap() * input.get(1).unwrap()]);
+
+        graph.input(node_1).unwrap().set(vec![1.0]);
+        graph.input(node_2).unwrap().set(vec![2.0]);
+        graph.add_children(node_2, node_1).unwrap();
+
+        assert_output_eq!(graph, node_2, [1.7], 1e-3);
+    }
+
+    #[test]
+    fn test_assert_graph_valid() {
+        let mut graph = Graph::new();
+        let x = graph.node(|input| input).input(vec![1.0]).build();
+        let sum = graph
+            .node(|input| vec![input[0] + input[1]])
+            .arity(2)
+            .child(x)
+            .unwrap()
+            .build();
+
+        assert_graph_valid!(Graph::new());
+        graph.add_children(sum, x).unwrap();
+        assert_graph_valid!(graph);
+    }
+
+    #[test]
+    #[should_panic(expected = "graph has 2 wiring problems\n  \
+                               node #2 expects 2 children, has 1\n    \
+                               path: #0 -> #1 -> #2\n  \
+                               node #3 has no input on port \"rhs\"\n    \
+                               path: #0 -> #1 -> #3")]
+    fn test_assert_graph_valid_diagnostics() {
+        let mut graph = Graph::new();
+        let x = graph.node(|input| input).input(vec![1.0]).build();
+        let y = graph.node(|input| input).child(x).unwrap().build();
+        graph
+            .node(|input| vec![input[0] + input[1]])
+            .arity(2)
+            .child(y)
+            .unwrap()
+            .build();
+        graph
+            .port_node(&["lhs", "rhs"], |inputs| Ok(inputs.port("lhs")?.to_vec()))
+            .port("lhs", y)
+            .unwrap()
+            .build();
+
+        assert_graph_valid!(graph);
+    }
+}
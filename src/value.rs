@@ -0,0 +1,7 @@
+use std::fmt::Debug;
+
+/// Element type carried between nodes. Graphs default to `f32`, any cloneable type such as
+/// `f64`, `i64` or a complex number works as well.
+pub trait Value: Clone + Debug + 'static {}
+
+impl<T: Clone + Debug + 'static> Value for T {}
@@ -0,0 +1,126 @@
+use std::ops::Range;
+
+use crate::value::Value;
+
+/// Window over a cached output. Slicing and striding only move the window bounds,
+/// values are copied out of the cache only when they are read.
+///
+/// Nodes read their children through views with ports, see [`Inputs::view`]. Functions of other
+/// nodes receive their input flattened into a new buffer.
+///
+/// [`Inputs::view`]: crate::Inputs::view
+///
+/// Like the output it reads, a view keeps the graph borrowed until it is dropped:
+///
+/// ```compile_fail
+/// let mut graph = computation_graph::Graph::new();
+/// let node = graph.node(|input| input).input(vec![1.0, 2.0]).build();
+/// let view = graph.view(node).unwrap();
+/// graph.input(node).unwrap().set(vec![3.0]);
+/// assert_eq!(view.get(0), Some(1.0));
+/// ```
+pub struct OutputView<'a, T = f32> {
+    output: &'a [T],
+    start: usize,
+    len: usize,
+    step: usize,
+}
+
+// Derived impls would require `T: Copy`, the view itself only holds a slice reference.
+impl<T> Clone for OutputView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for OutputView<'_, T> {}
+
+impl<'a, T: Value> OutputView<'a, T> {
+    pub(crate) fn new(output: &'a [T]) -> Self {
+        let len = output.len();
+        Self {
+            output,
+            start: 0,
+            len,
+            step: 1,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<T> {
+        if index < self.len {
+            Some(self.output[self.start + index * self.step].clone())
+        } else {
+            None
+        }
+    }
+
+    /// Range is relative to the current view and is clamped to its length.
+    pub fn slice(mut self, range: Range<usize>) -> Self {
+        let end = range.end.min(self.len);
+        let start = range.start.min(end);
+        self.start += start * self.step;
+        self.len = end - start;
+        self
+    }
+
+    pub fn step_by(mut self, step: usize) -> Self {
+        assert!(step > 0, "step must be positive");
+        self.len = self.len.div_ceil(step);
+        self.step *= step;
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        (0..self.len).map(move |index| self.output[self.start + index * self.step].clone())
+    }
+
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Graph;
+
+    #[test]
+    fn test_output_view() {
+        let mut graph = Graph::new();
+        let node = graph.add_node(|input| input);
+        graph
+            .input(node)
+            .unwrap()
+            .set((0..10).map(|x| x as f32).collect());
+
+        let view = graph.view(node).unwrap();
+        assert_eq!(view.len(), 10);
+
+        let view = view.slice(1..9).step_by(3);
+        assert_eq!(view.to_vec(), vec![1.0, 4.0, 7.0]);
+        assert_eq!(view.get(1), Some(4.0));
+        assert_eq!(view.get(3), None);
+
+        let view = view.slice(1..10);
+        assert_eq!(view.to_vec(), vec![4.0, 7.0]);
+
+        let view = graph.view(node).unwrap().step_by(4).slice(5..6);
+        assert!(view.is_empty());
+
+        let every_third = graph
+            .port_node(&["data"], |inputs| {
+                Ok(inputs.view("data")?.step_by(3).to_vec())
+            })
+            .port("data", node)
+            .unwrap()
+            .build();
+        assert_eq!(graph.compute(every_third).unwrap(), [0.0, 3.0, 6.0, 9.0]);
+    }
+}